@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mesh_data_tile::{decode_tile_minimal, inspect_tile};
+
+fuzz_target!(|data: &[u8]| {
+    // Malformed input must surface as Err, never as a panic.
+    let _ = inspect_tile(data);
+    let _ = decode_tile_minimal(data);
+});
@@ -0,0 +1,105 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use mesh_data_tile::{
+    decode_tile_minimal, CompressionMode, DType, Endianness, ExtensionOptions, MeshKind,
+    TileDimensions, TileEncodeInput,
+};
+
+// Keeps generated tiles small so the fuzzer spends its time on encoding paths rather
+// than allocation.
+const MAX_SIDE: u32 = 16;
+const MAX_BANDS: u8 = 4;
+
+#[derive(Debug)]
+struct FuzzTile {
+    tile_id: u64,
+    mesh_kind: MeshKind,
+    dtype: DType,
+    endianness: Endianness,
+    compression: CompressionMode,
+    dimensions: TileDimensions,
+    no_data: Option<f64>,
+    payload: Vec<u8>,
+    sparse: bool,
+}
+
+impl<'a> Arbitrary<'a> for FuzzTile {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mesh_kind = *u.choose(&[MeshKind::JisX0410, MeshKind::Xyz])?;
+        let tile_id = match mesh_kind {
+            MeshKind::Xyz => {
+                let zoom = u.int_in_range(0..=29_u64)?;
+                let quadkey = u.int_in_range(0..=(1_u64 << (2 * zoom)) - 1)?;
+                (zoom << 58) | quadkey
+            }
+            _ => u.arbitrary()?,
+        };
+        let dtype = *u.choose(&[
+            DType::Uint8,
+            DType::Int8,
+            DType::Uint16,
+            DType::Int16,
+            DType::Uint32,
+            DType::Int32,
+            DType::Float32,
+            DType::Float64,
+            DType::Uint24,
+            DType::Int24,
+        ])?;
+        let endianness = *u.choose(&[Endianness::Little, Endianness::Big])?;
+        let compression = *u.choose(&[CompressionMode::None, CompressionMode::DeflateRaw])?;
+        let dimensions = TileDimensions {
+            rows: u.int_in_range(1..=MAX_SIDE)?,
+            cols: u.int_in_range(1..=MAX_SIDE)?,
+            bands: u.int_in_range(1..=MAX_BANDS)?,
+        };
+        let payload_len =
+            dimensions.total_samples().expect("small dimensions") as usize * dtype.byte_size();
+        let payload = u.bytes(payload_len)?.to_vec();
+        // Integer no_data markers must fit the dtype; zero always does. NaN markers are
+        // float-only.
+        let is_float = matches!(dtype, DType::Float32 | DType::Float64);
+        let no_data = match u.int_in_range(0..=2_u8)? {
+            0 => None,
+            1 if is_float => Some(f64::NAN),
+            _ => Some(0.0),
+        };
+        let sparse = no_data.is_some() && u.arbitrary()?;
+
+        Ok(Self {
+            tile_id,
+            mesh_kind,
+            dtype,
+            endianness,
+            compression,
+            dimensions,
+            no_data,
+            payload,
+            sparse,
+        })
+    }
+}
+
+fuzz_target!(|tile: FuzzTile| {
+    let encoded = mesh_data_tile::encode_tile(TileEncodeInput {
+        tile_id: tile.tile_id,
+        mesh_kind: tile.mesh_kind,
+        dtype: tile.dtype,
+        endianness: tile.endianness,
+        compression: tile.compression,
+        dimensions: tile.dimensions,
+        no_data: tile.no_data,
+        payload: &tile.payload,
+        extensions: ExtensionOptions {
+            sparse: tile.sparse,
+            ..ExtensionOptions::default()
+        },
+    })
+    .expect("valid input must encode");
+
+    let decoded = decode_tile_minimal(&encoded.bytes).expect("encoded tile must decode");
+    assert_eq!(decoded.header, encoded.header);
+    assert_eq!(decoded.payload, tile.payload);
+});
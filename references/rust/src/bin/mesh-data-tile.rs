@@ -0,0 +1,116 @@
+use std::fs;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use mesh_data_tile::{
+    decode_payload_values, decode_tile_minimal, encode_tile, CompressionMode, ExtensionOptions,
+    TileEncodeInput,
+};
+
+const USAGE: &str = "\
+Usage:
+  mesh-data-tile inspect <file>
+  mesh-data-tile decode <file> --csv
+  mesh-data-tile convert <in> <out> --compression <none|deflate-raw>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let stdout = io::stdout();
+    match run(&args, &mut stdout.lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl<E: std::fmt::Display> From<E> for CliError {
+    fn from(err: E) -> Self {
+        Self::Failed(err.to_string())
+    }
+}
+
+fn run(args: &[String], out: &mut impl Write) -> Result<(), CliError> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["inspect", path] => {
+            let tile = decode_tile_minimal(&read(path)?)?;
+            writeln!(out, "{}", tile.header.describe())?;
+        }
+        ["decode", path, "--csv"] => {
+            let tile = decode_tile_minimal(&read(path)?)?;
+            let header = &tile.header;
+            let values = decode_payload_values(
+                header.dtype,
+                header.endianness,
+                &tile.payload,
+                header.no_data,
+            )?;
+            let cols = header.dimensions.cols as usize;
+            let bands = usize::from(header.dimensions.bands);
+            writeln!(out, "row,col,band,value")?;
+            for (idx, value) in values.iter().enumerate() {
+                let (pixel, band) = (idx / bands, idx % bands);
+                let (row, col) = (pixel / cols, pixel % cols);
+                match value {
+                    Some(value) => writeln!(out, "{row},{col},{band},{value}")?,
+                    None => writeln!(out, "{row},{col},{band},")?,
+                }
+            }
+        }
+        ["convert", input, output, "--compression", mode] => {
+            let compression = parse_compression(mode)?;
+            let tile = decode_tile_minimal(&read(input)?)?;
+            let header = &tile.header;
+            let encoded = encode_tile(TileEncodeInput {
+                tile_id: header.tile_id,
+                mesh_kind: header.mesh_kind,
+                dtype: header.dtype,
+                endianness: header.endianness,
+                compression,
+                dimensions: header.dimensions,
+                no_data: header.no_data,
+                payload: &tile.payload,
+                extensions: ExtensionOptions {
+                    sparse: header.sparse,
+                    metadata: &header.metadata,
+                    geotransform: header.geotransform,
+                    band_crc32: header.band_crc32.is_some(),
+                    payload_adler32: header.payload_adler32.is_some(),
+                    palette: header.palette.as_deref(),
+                },
+            })?;
+            fs::write(output, &encoded.bytes)
+                .map_err(|err| CliError::Failed(format!("Could not write {output}: {err}")))?;
+            writeln!(out, "{}", encoded.header.describe())?;
+        }
+        [] => return Err(CliError::Usage(String::from("missing command"))),
+        [command, ..] => {
+            return Err(CliError::Usage(format!(
+                "unrecognized arguments for `{command}`"
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &str) -> Result<Vec<u8>, CliError> {
+    fs::read(path).map_err(|err| CliError::Failed(format!("Could not read {path}: {err}")))
+}
+
+fn parse_compression(mode: &str) -> Result<CompressionMode, CliError> {
+    [CompressionMode::None, CompressionMode::DeflateRaw]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == mode)
+        .ok_or_else(|| CliError::Usage(format!("unknown compression `{mode}`")))
+}
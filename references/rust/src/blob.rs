@@ -0,0 +1,90 @@
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::decoder::{parse_header, parse_header_only};
+#[cfg(feature = "rayon")]
+use crate::{decode_tile_minimal, DecodedTile};
+#[cfg(feature = "std")]
+use crate::{encode_tile, TileEncodeInput, TileErrorCode};
+use crate::{Result, TileError, TileIndexEntry};
+
+#[cfg(feature = "std")]
+pub fn append_tile<W: Write>(writer: &mut W, input: TileEncodeInput<'_>) -> Result<usize> {
+    let encoded = encode_tile(input)?;
+    writer.write_all(&encoded.bytes).map_err(|err| {
+        TileError::new(
+            TileErrorCode::IoError,
+            format!("Could not write encoded tile: {err}"),
+        )
+    })?;
+    Ok(encoded.bytes.len())
+}
+
+pub fn build_blob_index(blob: &[u8]) -> Result<Vec<TileIndexEntry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0_usize;
+
+    while offset < blob.len() {
+        let parsed = parse_header(&blob[offset..]).map_err(|err| {
+            TileError::new(
+                err.code,
+                format!("Malformed tile at blob offset {offset}: {}", err.message),
+            )
+        })?;
+        let total_len = parsed.total_len();
+        entries.push(TileIndexEntry {
+            tile_id: parsed.header.tile_id,
+            offset,
+            total_len,
+        });
+        offset += total_len;
+    }
+
+    Ok(entries)
+}
+
+/// Decodes every tile in a blob of concatenated tiles, in blob order. Offsets are found
+/// sequentially from the headers first, so a malformed header fails before any decoding;
+/// the tiles are then decoded in parallel.
+#[cfg(feature = "rayon")]
+pub fn decode_blob_parallel(blob: &[u8]) -> Result<Vec<DecodedTile>> {
+    let entries = build_blob_index(blob)?;
+    entries
+        .par_iter()
+        .map(|entry| {
+            decode_tile_minimal(&blob[entry.offset..entry.offset + entry.total_len]).map_err(
+                |err| {
+                    TileError::new(
+                        err.code,
+                        format!(
+                            "Malformed tile at blob offset {}: {}",
+                            entry.offset, err.message
+                        ),
+                    )
+                },
+            )
+        })
+        .collect()
+}
+
+/// tile_ids that appear more than once in `tiles`, in ascending order and listed once
+/// each. Only the fixed headers are parsed and checked; payloads are not read.
+pub fn find_duplicate_tile_ids(tiles: &[&[u8]]) -> Result<Vec<u64>> {
+    let mut seen = BTreeSet::new();
+    let mut duplicates = BTreeSet::new();
+    for (index, tile) in tiles.iter().enumerate() {
+        let header = parse_header_only(tile).map_err(|err| {
+            TileError::new(err.code, format!("Malformed tile {index}: {}", err.message))
+        })?;
+        if !seen.insert(header.tile_id) {
+            duplicates.insert(header.tile_id);
+        }
+    }
+    Ok(duplicates.into_iter().collect())
+}
@@ -0,0 +1,97 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::consts::{HEADER_CHECKSUM_INPUT_LENGTH, TILE_FIXED_HEADER_LENGTH};
+
+// Single entry point for CRC-32 (IEEE, the `crc32fast` polynomial) so the backend can be
+// swapped. The `crc-fast` feature uses its SIMD/hardware-CRC implementation; both
+// backends produce identical checksums.
+
+#[cfg(not(feature = "crc-fast"))]
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    crc32fast::hash(bytes)
+}
+
+#[cfg(feature = "crc-fast")]
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    crc_fast::checksum(crc_fast::CrcAlgorithm::Crc32IsoHdlc, bytes) as u32
+}
+
+/// Header CRC-32 as stored at `HEADER_CHECKSUM_OFFSET`: CRC-32 (IEEE) of
+/// `header_bytes[..HEADER_CHECKSUM_INPUT_LENGTH]`, i.e. bytes `0..54`. The checksum field
+/// itself (bytes `54..58`) is not hashed, so its current contents do not affect the result.
+pub fn compute_header_crc32(header_bytes: &[u8; TILE_FIXED_HEADER_LENGTH]) -> u32 {
+    crc32(&header_bytes[..HEADER_CHECKSUM_INPUT_LENGTH])
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Crc32Hasher {
+    #[cfg(not(feature = "crc-fast"))]
+    inner: crc32fast::Hasher,
+    #[cfg(feature = "crc-fast")]
+    inner: crc_fast::Digest,
+}
+
+impl Default for Crc32Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32Hasher {
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(not(feature = "crc-fast"))]
+            inner: crc32fast::Hasher::new(),
+            #[cfg(feature = "crc-fast")]
+            inner: crc_fast::Digest::new(crc_fast::CrcAlgorithm::Crc32IsoHdlc),
+        }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        self.inner.update(bytes);
+    }
+
+    pub(crate) fn finalize(self) -> u32 {
+        #[cfg(not(feature = "crc-fast"))]
+        return self.inner.finalize();
+        #[cfg(feature = "crc-fast")]
+        return self.inner.finalize() as u32;
+    }
+}
+
+const ADLER32_MODULUS: u32 = 65_521;
+// Largest run of bytes whose sums cannot overflow a u32 before reducing (zlib's NMAX).
+const ADLER32_BLOCK: usize = 5_552;
+
+// Adler-32 as defined by RFC 1950, for interop with zlib-based systems.
+pub(crate) fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for block in bytes.chunks(ADLER32_BLOCK) {
+        for &byte in block {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= ADLER32_MODULUS;
+        b %= ADLER32_MODULUS;
+    }
+    (b << 16) | a
+}
+
+// CRC-32 of each band in `selected`, over that band's samples in payload order (every
+// `band_count`-th sample of `sample_size` bytes, starting at the band's index).
+pub(crate) fn band_crc32s(
+    payload: &[u8],
+    sample_size: usize,
+    band_count: u8,
+    selected: &[u8],
+) -> Vec<u32> {
+    let mut hashers = vec![Crc32Hasher::new(); selected.len()];
+    for pixel in payload.chunks_exact(sample_size * usize::from(band_count)) {
+        for (hasher, &band) in hashers.iter_mut().zip(selected) {
+            let start = usize::from(band) * sample_size;
+            hasher.update(&pixel[start..start + sample_size]);
+        }
+    }
+    hashers.into_iter().map(Crc32Hasher::finalize).collect()
+}
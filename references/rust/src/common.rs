@@ -1,54 +1,99 @@
-use crate::{DType, Endianness, MeshKind, Result, TileDimensions, TileError, TileErrorCode};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
-pub(crate) fn expected_payload_length(dimensions: TileDimensions, dtype: DType) -> Result<usize> {
-    let total_samples = dimensions.total_samples()?;
-    let byte_len = total_samples
-        .checked_mul(dtype.byte_size() as u64)
-        .ok_or_else(|| {
-            TileError::new(
-                TileErrorCode::InvalidPayloadLength,
-                "Payload length overflow.",
-            )
-        })?;
+use crate::consts::CUSTOM_MESH_KIND_MIN;
+#[cfg(feature = "std")]
+use crate::TileBounds;
+use crate::{
+    CastPolicy, DType, Endianness, MeshKind, Result, TileDimensions, TileError, TileErrorCode,
+    XyzTileId,
+};
 
+/// Raw payload byte length for `dimensions` and `dtype`:
+/// `rows * cols * bands * dtype.byte_size()`, checked for overflow.
+///
+/// ```
+/// use mesh_data_tile::{expected_payload_length, DType, TileDimensions};
+///
+/// let dims = TileDimensions { rows: 256, cols: 256, bands: 1 };
+/// assert_eq!(expected_payload_length(dims, DType::Uint16)?, 131_072);
+/// # Ok::<(), mesh_data_tile::TileError>(())
+/// ```
+pub fn expected_payload_length(dimensions: TileDimensions, dtype: DType) -> Result<usize> {
+    // Cannot overflow: at most 2^32 * 2^32 * 2^8 * 8 bytes.
+    let byte_len = u128::from(dimensions.rows)
+        * u128::from(dimensions.cols)
+        * u128::from(dimensions.bands)
+        * dtype.byte_size() as u128;
     usize::try_from(byte_len).map_err(|_| {
         TileError::new(
             TileErrorCode::InvalidPayloadLength,
-            "Payload length exceeds platform usize.",
+            format!(
+                "A {}x{}x{} {} payload would be {byte_len} bytes ({}), more than usize::MAX ({}) on this platform.",
+                dimensions.rows,
+                dimensions.cols,
+                dimensions.bands,
+                dtype.as_str(),
+                human_byte_size(byte_len),
+                usize::MAX
+            ),
         )
     })
 }
 
-pub(crate) fn read_u32_le(bytes: &[u8], offset: usize) -> Result<u32> {
-    let value_bytes = bytes.get(offset..offset + 4).ok_or_else(|| {
-        TileError::new(
-            TileErrorCode::InvalidHeaderLength,
-            "Could not read u32 field.",
-        )
-    })?;
-    let arr: [u8; 4] = value_bytes.try_into().map_err(|_| {
+// `1536` -> `1.5 KiB`, for error messages about impossible sizes.
+pub(crate) fn human_byte_size(bytes: u128) -> String {
+    const UNITS: [&str; 8] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB", "ZiB", "YiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+pub(crate) fn read_u8(bytes: &[u8], offset: usize) -> Result<u8> {
+    bytes.get(offset).copied().ok_or_else(|| {
         TileError::new(
             TileErrorCode::InvalidHeaderLength,
-            "Could not parse u32 field bytes.",
+            "Could not read u8 field.",
         )
-    })?;
-    Ok(u32::from_le_bytes(arr))
+    })
+}
+
+pub(crate) fn read_bytes<const N: usize>(bytes: &[u8], offset: usize) -> Result<[u8; N]> {
+    offset
+        .checked_add(N)
+        .and_then(|end| bytes.get(offset..end))
+        .and_then(|value_bytes| value_bytes.try_into().ok())
+        .ok_or_else(|| {
+            TileError::new(
+                TileErrorCode::InvalidHeaderLength,
+                format!("Could not read {N}-byte field at offset {offset}."),
+            )
+        })
+}
+
+pub(crate) fn read_u32_le(bytes: &[u8], offset: usize) -> Result<u32> {
+    read_bytes(bytes, offset).map(u32::from_le_bytes)
 }
 
 pub(crate) fn read_u64_le(bytes: &[u8], offset: usize) -> Result<u64> {
-    let value_bytes = bytes.get(offset..offset + 8).ok_or_else(|| {
-        TileError::new(
-            TileErrorCode::InvalidHeaderLength,
-            "Could not read u64 field.",
-        )
-    })?;
-    let arr: [u8; 8] = value_bytes.try_into().map_err(|_| {
-        TileError::new(
-            TileErrorCode::InvalidHeaderLength,
-            "Could not parse u64 field bytes.",
-        )
-    })?;
-    Ok(u64::from_le_bytes(arr))
+    read_bytes(bytes, offset).map(u64::from_le_bytes)
+}
+
+pub(crate) fn native_endianness() -> Endianness {
+    if cfg!(target_endian = "big") {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    }
 }
 
 pub(crate) fn pack_dtype_endian(dtype: DType, endianness: Endianness) -> u8 {
@@ -69,23 +114,251 @@ pub(crate) fn unpack_dtype_endian(value: u8) -> Result<(DType, Endianness)> {
     Ok((dtype, endianness))
 }
 
+/// Sample index of `(row, col, band)` in row-major, band-interleaved order (spec §5):
+/// `((row * cols) + col) * bands + band`. `None` if the arithmetic overflows `u64`;
+/// coordinates are not range-checked.
+pub(crate) fn sample_index(
+    dimensions: TileDimensions,
+    row: u32,
+    col: u32,
+    band: u8,
+) -> Option<u64> {
+    u64::from(row)
+        .checked_mul(u64::from(dimensions.cols))?
+        .checked_add(u64::from(col))?
+        .checked_mul(u64::from(dimensions.bands))?
+        .checked_add(u64::from(band))
+}
+
+/// Largest tile_id that passes validation for `mesh_kind`.
+///
+/// For `Xyz` that is zoom 29 with the last quadkey, `(29 << 58) | (4^29 - 1)`. `JisX0410`
+/// and `Custom` tile_ids are not range-checked, so their bound is `u64::MAX`.
+pub fn max_tile_id(mesh_kind: MeshKind) -> u64 {
+    match mesh_kind {
+        MeshKind::Xyz => (XYZ_MAX_ZOOM << XYZ_ZOOM_SHIFT) | ((1 << (2 * XYZ_MAX_ZOOM)) - 1),
+        MeshKind::JisX0410 | MeshKind::Custom(_) => u64::MAX,
+    }
+}
+
 pub(crate) fn validate_tile_id_for_mesh_kind(tile_id: u64, mesh_kind: MeshKind) -> Result<()> {
-    if mesh_kind == MeshKind::Xyz {
+    match mesh_kind {
+        MeshKind::JisX0410 => Ok(()),
+        MeshKind::Xyz => assert_valid_xyz_tile_id(tile_id),
+        MeshKind::Custom(code) if code >= CUSTOM_MESH_KIND_MIN => Ok(()),
+        MeshKind::Custom(code) => Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!("Custom mesh_kind code must be >= {CUSTOM_MESH_KIND_MIN}, got {code}."),
+        )),
+    }
+}
+
+/// Sort key that keeps spatially close tiles of one mesh kind close together.
+///
+/// - `Xyz`: the tile_id itself, i.e. by zoom, then quadkey (Z-order within a zoom).
+/// - `JisX0410`: by mesh level (coarser first, root `0` first of all), then row-major
+///   from the south-west corner: south to north, and west to east within a row. Codes
+///   that are not valid JIS X 0410 meshes sort after all valid ones, by tile_id.
+/// - `Custom`: the tile_id itself.
+///
+/// Keys are only comparable between tiles of the same mesh kind.
+pub fn tile_id_sort_key(tile_id: u64, mesh_kind: MeshKind) -> u64 {
+    match mesh_kind {
+        MeshKind::Xyz | MeshKind::Custom(_) => tile_id,
+        MeshKind::JisX0410 => match jis_grid_position(tile_id) {
+            Some((level, row, col, cols)) => (level << 56) | (row * cols + col),
+            None => (1 << 63) | tile_id.min((1 << 63) - 1),
+        },
+    }
+}
+
+// (level, row, col, cols at level) for a JIS X 0410 mesh code, where row/col count from the
+// south-west corner of the 1st-level mesh grid.
+fn jis_grid_position(code: u64) -> Option<(u64, u64, u64, u64)> {
+    if code == 0 {
+        return Some((0, 0, 0, 1));
+    }
+    let mut digits = [0_u64; 11];
+    let mut len = 0;
+    let mut rest = code;
+    while rest > 0 {
+        if len == digits.len() {
+            return None;
+        }
+        digits[len] = rest % 10;
+        rest /= 10;
+        len += 1;
+    }
+    digits[..len].reverse();
+    let digits = &digits[..len];
+    if !matches!(len, 4 | 6 | 8 | 9 | 10 | 11) {
+        return None;
+    }
+
+    // 1st mesh: 2-digit latitude and longitude codes on a 100x100 grid.
+    let mut row = digits[0] * 10 + digits[1];
+    let mut col = digits[2] * 10 + digits[3];
+    let mut cols = 100;
+    let mut level = 1;
+    // 2nd mesh: 8x8 subdivision; 3rd mesh: 10x10 subdivision.
+    for (idx, split) in [(4, 8), (6, 10)] {
+        if len <= idx {
+            break;
+        }
+        let (r, c) = (digits[idx], digits[idx + 1]);
+        if r >= split || c >= split {
+            return None;
+        }
+        row = row * split + r;
+        col = col * split + c;
+        cols *= split;
+        level += 1;
+    }
+    // Half, quarter, and eighth meshes: 1=SW, 2=SE, 3=NW, 4=NE.
+    for &quadrant in &digits[8.min(len)..] {
+        if !(1..=4).contains(&quadrant) {
+            return None;
+        }
+        row = row * 2 + (quadrant - 1) / 2;
+        col = col * 2 + (quadrant - 1) % 2;
+        cols *= 2;
+        level += 1;
+    }
+    Some((level, row, col, cols))
+}
+
+const XYZ_ZOOM_SHIFT: u32 = 58;
+const XYZ_MAX_ZOOM: u64 = 29;
+
+/// Parent of an XYZ tile_id (zoom - 1), or `None` at zoom 0.
+pub fn xyz_parent(tile_id: u64) -> Result<Option<u64>> {
+    assert_valid_xyz_tile_id(tile_id)?;
+    let (zoom, quadkey) = split_xyz_tile_id(tile_id);
+    if zoom == 0 {
+        return Ok(None);
+    }
+    Ok(Some(((zoom - 1) << XYZ_ZOOM_SHIFT) | (quadkey >> 2)))
+}
+
+/// The four children of an XYZ tile_id (zoom + 1) in quadkey digit order `0..=3`, or an
+/// empty `Vec` at the maximum zoom 29.
+pub fn xyz_children(tile_id: u64) -> Result<Vec<u64>> {
+    assert_valid_xyz_tile_id(tile_id)?;
+    let (zoom, quadkey) = split_xyz_tile_id(tile_id);
+    if zoom == XYZ_MAX_ZOOM {
+        return Ok(Vec::new());
+    }
+    Ok((0..4)
+        .map(|digit| ((zoom + 1) << XYZ_ZOOM_SHIFT) | (quadkey << 2) | digit)
+        .collect())
+}
+
+/// Geographic extent of an XYZ tile in EPSG:4326 degrees, from the standard Web Mercator
+/// slippy-map formula. Latitudes stay within about ±85.0511°, the Web Mercator limit.
+#[cfg(feature = "std")]
+pub fn xyz_tile_bounds(tile_id: u64) -> Result<TileBounds> {
+    let tile = XyzTileId::try_from(tile_id)?;
+    let size = f64::from(1_u32 << tile.zoom());
+    let lng = |x: u32| f64::from(x) / size * 360.0 - 180.0;
+    let lat = |y: u32| {
+        (core::f64::consts::PI * (1.0 - 2.0 * f64::from(y) / size))
+            .sinh()
+            .atan()
+            .to_degrees()
+    };
+    Ok(TileBounds {
+        west: lng(tile.x()),
+        south: lat(tile.y() + 1),
+        east: lng(tile.x() + 1),
+        north: lat(tile.y()),
+    })
+}
+
+impl XyzTileId {
+    /// Builds the tile_id for tile `(x, y)` at `zoom`; `x` and `y` must be `< 2^zoom`.
+    pub fn new(zoom: u8, x: u32, y: u32) -> Result<Self> {
+        if u64::from(zoom) > XYZ_MAX_ZOOM {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!("XYZ tile_id zoom must be <= 29, got {zoom}."),
+            ));
+        }
+        let size = 1_u64 << zoom;
+        if u64::from(x) >= size || u64::from(y) >= size {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!("XYZ tile ({x}, {y}) is outside zoom {zoom}."),
+            ));
+        }
+
+        // Each quadkey digit is `y_bit << 1 | x_bit`, most significant level first.
+        let mut quadkey = 0_u64;
+        for bit in (0..zoom).rev() {
+            let digit = u64::from((x >> bit) & 1) | u64::from((y >> bit) & 1) << 1;
+            quadkey = (quadkey << 2) | digit;
+        }
+        Ok(Self((u64::from(zoom) << XYZ_ZOOM_SHIFT) | quadkey))
+    }
+
+    pub fn zoom(self) -> u8 {
+        split_xyz_tile_id(self.0).0 as u8
+    }
+
+    pub fn x(self) -> u32 {
+        self.coordinates().0
+    }
+
+    pub fn y(self) -> u32 {
+        self.coordinates().1
+    }
+
+    pub fn raw(self) -> u64 {
+        self.0
+    }
+
+    fn coordinates(self) -> (u32, u32) {
+        let (zoom, quadkey) = split_xyz_tile_id(self.0);
+        let (mut x, mut y) = (0_u32, 0_u32);
+        for level in (0..zoom).rev() {
+            let digit = (quadkey >> (level * 2)) & 0b11;
+            x = (x << 1) | (digit & 0b01) as u32;
+            y = (y << 1) | (digit >> 1) as u32;
+        }
+        (x, y)
+    }
+}
+
+impl From<XyzTileId> for u64 {
+    fn from(tile_id: XyzTileId) -> Self {
+        tile_id.0
+    }
+}
+
+impl TryFrom<u64> for XyzTileId {
+    type Error = TileError;
+
+    fn try_from(tile_id: u64) -> Result<Self> {
         assert_valid_xyz_tile_id(tile_id)?;
+        Ok(Self(tile_id))
     }
-    Ok(())
+}
+
+fn split_xyz_tile_id(tile_id: u64) -> (u64, u64) {
+    (
+        tile_id >> XYZ_ZOOM_SHIFT,
+        tile_id & ((1_u64 << XYZ_ZOOM_SHIFT) - 1),
+    )
 }
 
 fn assert_valid_xyz_tile_id(tile_id: u64) -> Result<()> {
-    let zoom = tile_id >> 58;
-    if zoom > 29 {
+    let (zoom, quadkey) = split_xyz_tile_id(tile_id);
+    if tile_id > max_tile_id(MeshKind::Xyz) {
         return Err(TileError::new(
             TileErrorCode::InvalidFieldValue,
             format!("XYZ tile_id zoom must be <= 29, got {zoom}."),
         ));
     }
 
-    let quadkey = tile_id & ((1_u64 << 58) - 1);
     let max_quadkey = 1_u128 << (2 * zoom);
     if u128::from(quadkey) >= max_quadkey {
         return Err(TileError::new(
@@ -107,16 +380,49 @@ pub(crate) fn encode_no_data_field(
         return Ok((0, out));
     };
 
+    if value.is_nan() {
+        if !matches!(dtype, DType::Float32 | DType::Float64) {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "NaN no_data requires a float dtype, got {}.",
+                    dtype.as_str()
+                ),
+            ));
+        }
+        return Ok((2, out));
+    }
+
     if !value.is_finite() {
         return Err(TileError::new(
             TileErrorCode::InvalidFieldValue,
-            "no_data must be finite number or null.",
+            "no_data must be finite number, NaN, or null.",
         ));
     }
 
     let value_byte_size = dtype.byte_size();
     let mut encoded = vec![0_u8; value_byte_size];
-    write_numeric_value(dtype, endianness, value, false, &mut encoded)?;
+    write_numeric_value(dtype, endianness, value, false, &mut encoded).map_err(|err| {
+        TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "no_data {value} is not representable as {}: {}",
+                dtype.as_str(),
+                err.message
+            ),
+        )
+    })?;
+    // Integer writes are already exact; float32 can round silently.
+    let stored = read_numeric_value(dtype, endianness, &encoded)?;
+    if stored != value {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "no_data {value} is not exactly representable as {}; it would be stored as {stored}.",
+                dtype.as_str()
+            ),
+        ));
+    }
 
     match endianness {
         Endianness::Little => out[..value_byte_size].copy_from_slice(&encoded),
@@ -126,12 +432,27 @@ pub(crate) fn encode_no_data_field(
     Ok((1, out))
 }
 
+/// Rejects `no_data_kind` codes this reader does not understand (spec §4.5), listing the
+/// supported kinds.
+pub(crate) fn validate_no_data_kind(kind: u8) -> Result<()> {
+    if matches!(kind, 0..=2) {
+        return Ok(());
+    }
+    Err(TileError::new(
+        TileErrorCode::InvalidFieldValue,
+        format!(
+            "Unsupported no_data_kind {kind}. Supported kinds: 0 (no no-data), 1 (numeric marker), 2 (NaN)."
+        ),
+    ))
+}
+
 pub(crate) fn decode_no_data_field(
     kind: u8,
     no_data_value_raw: [u8; 8],
     dtype: DType,
     endianness: Endianness,
 ) -> Result<Option<f64>> {
+    validate_no_data_kind(kind)?;
     if kind == 0 {
         if no_data_value_raw.iter().any(|byte| *byte != 0) {
             return Err(TileError::new(
@@ -142,11 +463,23 @@ pub(crate) fn decode_no_data_field(
         return Ok(None);
     }
 
-    if kind != 1 {
-        return Err(TileError::new(
-            TileErrorCode::InvalidFieldValue,
-            format!("Unsupported no_data kind {kind}."),
-        ));
+    if kind == 2 {
+        if !matches!(dtype, DType::Float32 | DType::Float64) {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "no_data_kind=2 (NaN) requires a float dtype, got {}.",
+                    dtype.as_str()
+                ),
+            ));
+        }
+        if no_data_value_raw.iter().any(|byte| *byte != 0) {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                "no_data_value must be zero when no_data_kind=2.",
+            ));
+        }
+        return Ok(Some(f64::NAN));
     }
 
     let value_size = dtype.byte_size();
@@ -189,6 +522,11 @@ pub(crate) fn decode_no_data_field(
     Ok(Some(value))
 }
 
+// A NaN marker matches every NaN sample regardless of its payload bits.
+pub(crate) fn is_no_data(value: f64, marker: f64) -> bool {
+    value.to_bits() == marker.to_bits() || (marker.is_nan() && value.is_nan())
+}
+
 pub(crate) fn write_numeric_value(
     dtype: DType,
     endianness: Endianness,
@@ -244,6 +582,15 @@ pub(crate) fn write_numeric_value(
             };
             out.copy_from_slice(&bytes);
         }
+        DType::Uint24 => {
+            let v = validate_integer_range(value, 0.0, UINT24_MAX)? as u32;
+            write_u24(v, endianness, out);
+        }
+        DType::Int24 => {
+            let v = validate_integer_range(value, INT24_MIN, INT24_MAX)? as i32;
+            // The low 24 bits of the two's-complement value.
+            write_u24(v as u32, endianness, out);
+        }
         DType::Float32 => {
             if !(value.is_finite() || allow_float_nan && value.is_nan()) {
                 return Err(TileError::new(
@@ -350,6 +697,12 @@ pub(crate) fn read_numeric_value(
             };
             f64::from(v)
         }
+        DType::Uint24 => f64::from(read_u24(bytes, endianness, dtype)?),
+        DType::Int24 => {
+            // Shift the sign bit (bit 23) into bit 31, then sign-extend back down.
+            let v = read_u24(bytes, endianness, dtype)?;
+            f64::from(((v << 8) as i32) >> 8)
+        }
         DType::Float32 => {
             let arr: [u8; 4] = bytes.try_into().map_err(|_| {
                 TileError::new(
@@ -380,14 +733,38 @@ pub(crate) fn read_numeric_value(
     Ok(value)
 }
 
-fn validate_integer_range(value: f64, min: f64, max: f64) -> Result<f64> {
+pub(crate) const UINT24_MAX: f64 = 16_777_215.0;
+pub(crate) const INT24_MIN: f64 = -8_388_608.0;
+pub(crate) const INT24_MAX: f64 = 8_388_607.0;
+
+fn write_u24(value: u32, endianness: Endianness, out: &mut [u8]) {
+    match endianness {
+        Endianness::Little => out.copy_from_slice(&value.to_le_bytes()[..3]),
+        Endianness::Big => out.copy_from_slice(&value.to_be_bytes()[1..]),
+    }
+}
+
+fn read_u24(bytes: &[u8], endianness: Endianness, dtype: DType) -> Result<u32> {
+    let [a, b, c]: [u8; 3] = bytes.try_into().map_err(|_| {
+        TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!("Could not parse {} payload value.", dtype.as_str()),
+        )
+    })?;
+    Ok(match endianness {
+        Endianness::Little => u32::from_le_bytes([a, b, c, 0]),
+        Endianness::Big => u32::from_be_bytes([0, a, b, c]),
+    })
+}
+
+pub(crate) fn validate_integer_range(value: f64, min: f64, max: f64) -> Result<f64> {
     if !value.is_finite() {
         return Err(TileError::new(
             TileErrorCode::InvalidFieldValue,
             format!("Non-finite value: {value}"),
         ));
     }
-    if value.fract() != 0.0 {
+    if !is_integral(value) {
         return Err(TileError::new(
             TileErrorCode::InvalidFieldValue,
             format!("Non-integer value: {value}"),
@@ -401,3 +778,73 @@ fn validate_integer_range(value: f64, min: f64, max: f64) -> Result<f64> {
     }
     Ok(value)
 }
+
+/// `(min, max)` of an integer dtype, or `None` for float dtypes.
+fn integer_range(dtype: DType) -> Option<(f64, f64)> {
+    match dtype {
+        DType::Uint8 => Some((0.0, u8::MAX as f64)),
+        DType::Int8 => Some((i8::MIN as f64, i8::MAX as f64)),
+        DType::Uint16 => Some((0.0, u16::MAX as f64)),
+        DType::Int16 => Some((i16::MIN as f64, i16::MAX as f64)),
+        DType::Uint24 => Some((0.0, UINT24_MAX)),
+        DType::Int24 => Some((INT24_MIN, INT24_MAX)),
+        DType::Uint32 => Some((0.0, u32::MAX as f64)),
+        DType::Int32 => Some((i32::MIN as f64, i32::MAX as f64)),
+        DType::Float32 | DType::Float64 => None,
+    }
+}
+
+pub(crate) fn cast_value(dtype: DType, value: f64, policy: CastPolicy) -> Result<f64> {
+    let Some((min, max)) = integer_range(dtype) else {
+        let value = match (dtype, policy) {
+            (DType::Float32, CastPolicy::Clamp) if !value.is_nan() => {
+                value.clamp(-(f32::MAX as f64), f32::MAX as f64)
+            }
+            _ => value,
+        };
+        let mut scratch = [0_u8; 8];
+        write_numeric_value(
+            dtype,
+            Endianness::Little,
+            value,
+            true,
+            &mut scratch[..dtype.byte_size()],
+        )?;
+        return Ok(value);
+    };
+
+    let value = match policy {
+        CastPolicy::Reject => value,
+        CastPolicy::Round => round_half_even(value),
+        CastPolicy::Clamp if value.is_nan() => value,
+        CastPolicy::Clamp => round_half_even(value).clamp(min, max),
+    };
+    validate_integer_range(value, min, max)
+}
+
+// `f64::round_ties_even` needs std. Non-finite values are returned unchanged.
+fn round_half_even(value: f64) -> f64 {
+    const EXACT_INTEGER_BOUND: f64 = 4_503_599_627_370_496.0;
+    if !(-EXACT_INTEGER_BOUND..=EXACT_INTEGER_BOUND).contains(&value) {
+        return value;
+    }
+    let truncated = value as i64;
+    let diff = value - truncated as f64;
+    let step = if diff > 0.0 { 1 } else { -1 };
+    let rounds_away = diff.abs() > 0.5 || (diff.abs() == 0.5 && truncated % 2 != 0);
+    if rounds_away {
+        (truncated + step) as f64
+    } else {
+        truncated as f64
+    }
+}
+
+fn is_integral(value: f64) -> bool {
+    // Every finite f64 with magnitude >= 2^52 is already an integer; below that the
+    // round trip through i64 is exact. `f64::fract` is not available without `std`.
+    const EXACT_INTEGER_BOUND: f64 = 4_503_599_627_370_496.0;
+    if !(-EXACT_INTEGER_BOUND..=EXACT_INTEGER_BOUND).contains(&value) {
+        return true;
+    }
+    (value as i64) as f64 == value
+}
@@ -1,20 +1,60 @@
 pub const TILE_FIXED_HEADER_LENGTH: usize = 58;
 pub const TILE_VERSION_MAJOR: u8 = 1;
+/// Minor version written by this encoder. Readers accept any minor within the major.
+pub const TILE_VERSION_MINOR: u8 = 0;
+/// First `mesh_kind` code reserved for experimental mesh systems (`MeshKind::Custom`).
+pub const CUSTOM_MESH_KIND_MIN: u8 = 200;
 
 pub(crate) const MAGIC: [u8; 4] = *b"MTI1";
-pub(crate) const HEADER_CHECKSUM_OFFSET: usize = 54;
-pub(crate) const HEADER_CHECKSUM_INPUT_LENGTH: usize = HEADER_CHECKSUM_OFFSET;
+pub const HEADER_CHECKSUM_OFFSET: usize = 54;
+pub const HEADER_CHECKSUM_INPUT_LENGTH: usize = HEADER_CHECKSUM_OFFSET;
 
-pub(crate) const OFFSET_FORMAT_MAJOR: usize = 4;
-pub(crate) const OFFSET_TILE_ID: usize = 5;
-pub(crate) const OFFSET_MESH_KIND: usize = 13;
-pub(crate) const OFFSET_DTYPE_ENDIAN: usize = 14;
-pub(crate) const OFFSET_COMPRESSION: usize = 15;
-pub(crate) const OFFSET_ROWS: usize = 16;
-pub(crate) const OFFSET_COLS: usize = 20;
-pub(crate) const OFFSET_BANDS: usize = 24;
-pub(crate) const OFFSET_NO_DATA_KIND: usize = 25;
-pub(crate) const OFFSET_NO_DATA_VALUE: usize = 26;
-pub(crate) const OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH: usize = 34;
-pub(crate) const OFFSET_COMPRESSED_PAYLOAD_LENGTH: usize = 42;
-pub(crate) const OFFSET_PAYLOAD_CHECKSUM: usize = 50;
+pub const OFFSET_FORMAT_MAJOR: usize = 4;
+pub const OFFSET_TILE_ID: usize = 5;
+pub const OFFSET_MESH_KIND: usize = 13;
+pub const OFFSET_DTYPE_ENDIAN: usize = 14;
+pub const OFFSET_COMPRESSION: usize = 15;
+pub const OFFSET_ROWS: usize = 16;
+pub const OFFSET_COLS: usize = 20;
+pub const OFFSET_BANDS: usize = 24;
+pub const OFFSET_NO_DATA_KIND: usize = 25;
+pub const OFFSET_NO_DATA_VALUE: usize = 26;
+pub const OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH: usize = 34;
+pub const OFFSET_COMPRESSED_PAYLOAD_LENGTH: usize = 42;
+pub const OFFSET_PAYLOAD_CHECKSUM: usize = 50;
+
+// (offset, size) of every fixed header field in byte order.
+pub(crate) const HEADER_FIELDS: [(usize, usize); 15] = [
+    (0, MAGIC.len()),
+    (OFFSET_FORMAT_MAJOR, 1),
+    (OFFSET_TILE_ID, 8),
+    (OFFSET_MESH_KIND, 1),
+    (OFFSET_DTYPE_ENDIAN, 1),
+    (OFFSET_COMPRESSION, 1),
+    (OFFSET_ROWS, 4),
+    (OFFSET_COLS, 4),
+    (OFFSET_BANDS, 1),
+    (OFFSET_NO_DATA_KIND, 1),
+    (OFFSET_NO_DATA_VALUE, 8),
+    (OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH, 8),
+    (OFFSET_COMPRESSED_PAYLOAD_LENGTH, 8),
+    (OFFSET_PAYLOAD_CHECKSUM, 4),
+    (HEADER_CHECKSUM_OFFSET, 4),
+];
+
+const _: () = assert_header_layout();
+
+const fn assert_header_layout() {
+    let mut end = 0;
+    let mut idx = 0;
+    while idx < HEADER_FIELDS.len() {
+        let (offset, size) = HEADER_FIELDS[idx];
+        assert!(offset == end, "header fields must be contiguous");
+        end = offset + size;
+        idx += 1;
+    }
+    assert!(
+        end == TILE_FIXED_HEADER_LENGTH,
+        "header fields must fill the fixed header"
+    );
+}
@@ -0,0 +1,25 @@
+use crate::common::pack_dtype_endian;
+use crate::DecodedTile;
+
+// Bumped if the hashed layout ever changes, so old and new hashes cannot collide.
+const DOMAIN: &[u8] = b"mesh-data-tile content v1";
+
+impl DecodedTile {
+    /// BLAKE3 hash of the tile's content: dtype, endianness, dimensions, no_data, and the
+    /// decoded payload. The tile_id, compression, stored lengths, checksums, and extension
+    /// trailer are not hashed, so re-encoding the same data yields the same hash.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let header = &self.header;
+        let dimensions = header.dimensions;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(DOMAIN);
+        hasher.update(&[pack_dtype_endian(header.dtype, header.endianness)]);
+        hasher.update(&dimensions.rows.to_le_bytes());
+        hasher.update(&dimensions.cols.to_le_bytes());
+        hasher.update(&[dimensions.bands, header.no_data_kind]);
+        hasher.update(&header.no_data_value_raw);
+        hasher.update(&(self.payload.len() as u64).to_le_bytes());
+        hasher.update(&self.payload);
+        *hasher.finalize().as_bytes()
+    }
+}
@@ -1,38 +1,234 @@
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io::Read;
 
-use crc32fast::hash as crc32;
-use flate2::read::DeflateDecoder;
+#[cfg(feature = "std")]
+use flate2::read::{DeflateDecoder, ZlibDecoder};
 
+use crate::checksum::{adler32, band_crc32s, compute_header_crc32, crc32};
 use crate::common::{
-    decode_no_data_field, expected_payload_length, read_numeric_value, read_u32_le, read_u64_le,
-    unpack_dtype_endian, validate_tile_id_for_mesh_kind,
+    decode_no_data_field, expected_payload_length, is_no_data, read_bytes, read_numeric_value,
+    read_u32_le, read_u64_le, read_u8, sample_index, unpack_dtype_endian, validate_no_data_kind,
+    validate_tile_id_for_mesh_kind,
 };
 use crate::consts::{
-    HEADER_CHECKSUM_INPUT_LENGTH, HEADER_CHECKSUM_OFFSET, MAGIC, OFFSET_BANDS, OFFSET_COLS,
-    OFFSET_COMPRESSED_PAYLOAD_LENGTH, OFFSET_COMPRESSION, OFFSET_DTYPE_ENDIAN, OFFSET_FORMAT_MAJOR,
-    OFFSET_MESH_KIND, OFFSET_NO_DATA_KIND, OFFSET_NO_DATA_VALUE, OFFSET_PAYLOAD_CHECKSUM,
-    OFFSET_ROWS, OFFSET_TILE_ID, OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH, TILE_FIXED_HEADER_LENGTH,
+    HEADER_CHECKSUM_OFFSET, MAGIC, OFFSET_BANDS, OFFSET_COLS, OFFSET_COMPRESSED_PAYLOAD_LENGTH,
+    OFFSET_COMPRESSION, OFFSET_DTYPE_ENDIAN, OFFSET_FORMAT_MAJOR, OFFSET_MESH_KIND,
+    OFFSET_NO_DATA_KIND, OFFSET_NO_DATA_VALUE, OFFSET_PAYLOAD_CHECKSUM, OFFSET_ROWS,
+    OFFSET_TILE_ID, OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH, TILE_FIXED_HEADER_LENGTH,
     TILE_VERSION_MAJOR,
 };
+#[cfg(feature = "std")]
+use crate::encoder::estimate_entropy;
+use crate::extension::{parse_extensions, validate_palette};
+use crate::sparse::expand_sparse_payload;
 use crate::{
-    CompressionMode, DType, DecodedTile, Endianness, MeshKind, Result, TileDimensions, TileError,
-    TileErrorCode, TileHeader,
+    CompressionMode, DType, DecodeOptions, DecodedTile, Endianness, IntegrityFields, LayoutHint,
+    MeshKind, Result, TileDimensions, TileError, TileErrorCode, TileHeader,
 };
 
 #[derive(Debug)]
-struct ParsedHeader {
-    header: TileHeader,
-    compressed_payload_len: usize,
-    uncompressed_payload_len: usize,
+pub(crate) struct ParsedHeader {
+    pub(crate) header: TileHeader,
+    pub(crate) compressed_payload_len: usize,
+    pub(crate) uncompressed_payload_len: usize,
+    pub(crate) extension_len: usize,
 }
 
+impl ParsedHeader {
+    pub(crate) fn total_len(&self) -> usize {
+        TILE_FIXED_HEADER_LENGTH + self.compressed_payload_len + self.extension_len
+    }
+}
+
+pub fn is_mesh_data_tile(bytes: &[u8]) -> bool {
+    bytes.len() >= TILE_FIXED_HEADER_LENGTH
+        && bytes[0..4] == MAGIC
+        && bytes[OFFSET_FORMAT_MAJOR] == TILE_VERSION_MAJOR
+}
+
+/// Guesses where the fixed header sits, for triaging files that fail with `InvalidMagic`.
+/// Only the magic bytes are checked; nothing is decoded or validated.
+pub fn detect_layout(bytes: &[u8]) -> LayoutHint {
+    if bytes.starts_with(&MAGIC) {
+        return LayoutHint::HeaderFirst;
+    }
+    match bytes.len().checked_sub(TILE_FIXED_HEADER_LENGTH) {
+        Some(start) if bytes[start..].starts_with(&MAGIC) => LayoutHint::HeaderLast,
+        _ => LayoutHint::Unrecognized,
+    }
+}
+
+/// Parses and validates the header. When `bytes` holds the whole payload, the extension
+/// trailer after it is parsed too. When the payload is cut short (e.g. a range request
+/// for the first few hundred bytes) it behaves like [`parse_header_only`]: the fields
+/// from the trailer are unknown and left at their defaults, so a sparse tile reports
+/// `sparse: false`. Fetch the whole tile before relying on them.
 pub fn inspect_tile(bytes: &[u8]) -> Result<TileHeader> {
-    let parsed = parse_header(bytes)?;
-    Ok(parsed.header)
+    let parsed = parse_fixed_header(bytes, true)?;
+    if bytes.len() - TILE_FIXED_HEADER_LENGTH < parsed.compressed_payload_len {
+        return Ok(parsed.header);
+    }
+    Ok(parse_extensions_into(parsed, bytes)?.header)
+}
+
+/// Parses and validates the 58-byte fixed header without requiring the payload. Bytes
+/// after the header are ignored, so the fields from the extension trailer (`sparse`,
+/// `metadata`, `geotransform`, `band_crc32`, `payload_adler32`, `palette`, and
+/// `format_minor`) are unknown and left at their defaults, even for a sparse tile. Use
+/// [`inspect_tile`] on the whole tile to read them.
+pub fn parse_header_only(bytes: &[u8]) -> Result<TileHeader> {
+    Ok(parse_fixed_header(bytes, true)?.header)
+}
+
+/// Returns the reserved (unassigned) bytes of a validated fixed header.
+///
+/// MTI1 v1 has no reserved header bytes: every byte of the 58-byte header belongs to a
+/// field (checked at compile time against the offset table), so this always returns an
+/// empty `Vec` for v1 tiles. New optional data is carried in the extension trailer
+/// instead, and a change to the fixed header requires a new `format_major`.
+pub fn inspect_tile_reserved(bytes: &[u8]) -> Result<Vec<u8>> {
+    parse_header(bytes)?;
+    Ok(Vec::new())
+}
+
+pub fn verify_identity(
+    bytes: &[u8],
+    expected_tile_id: u64,
+    expected_dims: TileDimensions,
+) -> Result<()> {
+    let header = parse_header(bytes)?.header;
+    if header.tile_id != expected_tile_id {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "tile_id mismatch. expected={expected_tile_id} got={}",
+                header.tile_id
+            ),
+        ));
+    }
+    if header.dimensions != expected_dims {
+        let got = header.dimensions;
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "dimensions mismatch. expected={}x{}x{} got={}x{}x{}",
+                expected_dims.rows,
+                expected_dims.cols,
+                expected_dims.bands,
+                got.rows,
+                got.cols,
+                got.bands
+            ),
+        ));
+    }
+    Ok(())
 }
 
 pub fn decode_tile_minimal(bytes: &[u8]) -> Result<DecodedTile> {
-    let parsed = parse_header(bytes)?;
+    decode_tile_with_options(bytes, &DecodeOptions::default())
+}
+
+pub fn decode_tile_with_options(bytes: &[u8], options: &DecodeOptions) -> Result<DecodedTile> {
+    Ok(decode_tile_borrowed(bytes, options)?.into_decoded())
+}
+
+/// Decodes the tile at the start of `bytes` and returns it with the bytes that follow it
+/// (after the payload and any extension trailer), e.g. the next tile in a blob.
+pub fn decode_tile_with_remainder(bytes: &[u8]) -> Result<(DecodedTile, &[u8])> {
+    let tile = decode_tile_borrowed(bytes, &DecodeOptions::default())?;
+    let remainder = &bytes[tile.total_len..];
+    Ok((tile.into_decoded(), remainder))
+}
+
+pub(crate) struct BorrowedTile<'a> {
+    pub(crate) header: TileHeader,
+    // Borrows from the input when the payload is stored uncompressed and dense.
+    pub(crate) payload: Cow<'a, [u8]>,
+    // Bytes consumed from the input, including any extension trailer.
+    pub(crate) total_len: usize,
+    // Stored bytes of a compressed payload, when the options ask to retain them.
+    pub(crate) compressed_payload: Option<&'a [u8]>,
+    pub(crate) payload_crc_valid: bool,
+}
+
+impl BorrowedTile<'_> {
+    fn into_decoded(self) -> DecodedTile {
+        DecodedTile {
+            header: self.header,
+            payload: self.payload.into_owned(),
+            compressed_payload: self.compressed_payload.map(<[u8]>::to_vec),
+            payload_crc_valid: self.payload_crc_valid,
+        }
+    }
+}
+
+/// Decodes and verifies a tile like [`decode_tile_minimal`], writing the payload into
+/// `payload_buf` (cleared first) instead of a fresh `Vec`. Reusing one buffer across calls
+/// avoids a payload allocation per tile once it has grown large enough; sparse tiles still
+/// allocate while expanding. The buffer's contents are unspecified after an error.
+pub fn decode_tile_into(bytes: &[u8], payload_buf: &mut Vec<u8>) -> Result<TileHeader> {
+    let tile = decode_tile_buffered(bytes, &DecodeOptions::default(), payload_buf)?;
+    match tile.payload {
+        Cow::Borrowed(payload) => {
+            payload_buf.clear();
+            payload_buf.extend_from_slice(payload);
+        }
+        Cow::Owned(payload) => *payload_buf = payload,
+    }
+    Ok(tile.header)
+}
+
+// Decodes and verifies the tile at the start of `bytes`.
+pub(crate) fn decode_tile_borrowed<'a>(
+    bytes: &'a [u8],
+    options: &DecodeOptions,
+) -> Result<BorrowedTile<'a>> {
+    decode_tile_buffered(bytes, options, &mut Vec::new())
+}
+
+// Compressed payloads are inflated into `scratch`, whose allocation moves into the
+// returned payload.
+fn decode_tile_buffered<'a>(
+    bytes: &'a [u8],
+    options: &DecodeOptions,
+    scratch: &mut Vec<u8>,
+) -> Result<BorrowedTile<'a>> {
+    decode_parsed_tile(bytes, parse_header_with(bytes, options)?, options, scratch)
+}
+
+// Decodes the payload of a tile whose header `parsed` was already read from `bytes`.
+fn decode_parsed_tile<'a>(
+    bytes: &'a [u8],
+    parsed: ParsedHeader,
+    options: &DecodeOptions,
+    scratch: &mut Vec<u8>,
+) -> Result<BorrowedTile<'a>> {
+    if !options.allow_trailing_bytes && parsed.total_len() != bytes.len() {
+        return Err(TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!(
+                "{} trailing bytes after the tile.",
+                bytes.len() - parsed.total_len()
+            ),
+        ));
+    }
+    if let Some(max) = options.max_uncompressed_bytes {
+        let dense_len = expected_payload_length(parsed.header.dimensions, parsed.header.dtype)?;
+        let largest = parsed
+            .header
+            .payload_uncompressed_bytes
+            .max(dense_len as u64);
+        if largest > max {
+            return Err(TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                format!("Payload of {largest} bytes exceeds max_uncompressed_bytes={max}."),
+            ));
+        }
+    }
 
     let payload_end = TILE_FIXED_HEADER_LENGTH
         .checked_add(parsed.compressed_payload_len)
@@ -44,9 +240,20 @@ pub fn decode_tile_minimal(bytes: &[u8]) -> Result<DecodedTile> {
         })?;
 
     let stored_payload = &bytes[TILE_FIXED_HEADER_LENGTH..payload_end];
-    let payload = decompress_payload(parsed.header.compression, stored_payload)?;
+    // Inflating stops past the declared uncompressed length, so a forged header cannot
+    // make a small stream expand without bound. Only when the CRC may override a wrong
+    // length field is the dense length for the dimensions allowed instead; that length
+    // already passed `max_uncompressed_bytes` above when a limit is set.
+    let mut inflate_limit = parsed.uncompressed_payload_len;
+    if options.trust_payload_crc_over_length && !parsed.header.sparse {
+        let dense_len = expected_payload_length(parsed.header.dimensions, parsed.header.dtype)?;
+        inflate_limit = inflate_limit.max(dense_len);
+    }
+    let payload = decompress_payload(&parsed.header, stored_payload, inflate_limit, scratch)?;
 
-    if payload.len() != parsed.uncompressed_payload_len {
+    if payload.len() != parsed.uncompressed_payload_len
+        && !only_length_field_is_wrong(&parsed, &payload, options)?
+    {
         return Err(TileError::new(
             TileErrorCode::InvalidPayloadLength,
             format!(
@@ -57,33 +264,149 @@ pub fn decode_tile_minimal(bytes: &[u8]) -> Result<DecodedTile> {
         ));
     }
 
-    let payload_crc32 = crc32(&payload);
-    if payload_crc32 != parsed.header.payload_crc32 {
+    let retain_compressed =
+        options.retain_compressed_payload && parsed.header.compression != CompressionMode::None;
+    let mut payload_crc_valid = true;
+    if options.verify_payload_crc32 || retain_compressed {
+        match verify_payload_checksums(&parsed.header, &payload) {
+            Err(_) if options.return_payload_on_crc_mismatch => payload_crc_valid = false,
+            result => result?,
+        }
+    }
+
+    let payload = if parsed.header.sparse {
+        Cow::Owned(expand_sparse_payload(
+            &payload,
+            parsed.header.dimensions,
+            parsed.header.dtype,
+            parsed.header.endianness,
+            parsed.header.no_data,
+        )?)
+    } else {
+        payload
+    };
+
+    if options.verify_dimensions_match_payload {
+        let expected_uncompressed_len =
+            expected_payload_length(parsed.header.dimensions, parsed.header.dtype)?;
+        if payload.len() != expected_uncompressed_len {
+            return Err(TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                format!(
+                    "Decoded payload length mismatch. expected={expected_uncompressed_len} got={}",
+                    payload.len()
+                ),
+            ));
+        }
+    }
+
+    Ok(BorrowedTile {
+        compressed_payload: retain_compressed.then_some(stored_payload),
+        payload_crc_valid,
+        total_len: parsed.total_len(),
+        header: parsed.header,
+        payload,
+    })
+}
+
+/// Decodes a tile and keeps only the requested bands, in the order given. The whole
+/// payload is still decompressed; the returned header describes the narrowed payload
+/// stored uncompressed.
+///
+/// When the tile stores per-band CRCs, only the requested bands are checksummed instead
+/// of the whole payload: the whole-payload CRC-32 and Adler-32 are both skipped, so
+/// damage confined to other bands goes unnoticed. The header and trailer checksums and
+/// the payload lengths are always checked.
+pub fn decode_tile_bands(bytes: &[u8], bands: &[u8]) -> Result<DecodedTile> {
+    let parsed = parse_header(bytes)?;
+    let options = DecodeOptions {
+        verify_payload_crc32: parsed.header.band_crc32.is_none(),
+        ..DecodeOptions::default()
+    };
+    let tile = decode_parsed_tile(bytes, parsed, &options, &mut Vec::new())?.into_decoded();
+    let Some(expected) = &tile.header.band_crc32 else {
+        return tile.select_bands(bands);
+    };
+    let narrowed = tile.select_bands(bands)?;
+    let actual = band_crc32s(
+        &tile.payload,
+        tile.header.dtype.byte_size(),
+        tile.header.dimensions.bands,
+        bands,
+    );
+    for (&band, actual) in bands.iter().zip(actual) {
+        let expected = expected[usize::from(band)];
+        if actual != expected {
+            return Err(TileError::new(
+                TileErrorCode::PayloadChecksumMismatch,
+                format!(
+                    "Band {band} checksum mismatch. expected={expected:08x} actual={actual:08x}"
+                ),
+            ));
+        }
+    }
+    Ok(narrowed)
+}
+
+/// Reads one sample of an uncompressed tile straight from `bytes`, e.g. a memory-mapped
+/// archive. Only the header and trailer are parsed; the payload is neither copied nor
+/// checksummed. Compressed tiles return `UnsupportedCompression` and sparse tiles
+/// `InvalidFieldValue`, since locating a sample needs the whole payload.
+pub fn read_sample_uncompressed(bytes: &[u8], row: u32, col: u32, band: u8) -> Result<f64> {
+    let parsed = parse_header(bytes)?;
+    let header = &parsed.header;
+    if header.compression != CompressionMode::None {
         return Err(TileError::new(
-            TileErrorCode::PayloadChecksumMismatch,
+            TileErrorCode::UnsupportedCompression,
             format!(
-                "Payload checksum mismatch. expected={:08x} actual={payload_crc32:08x}",
-                parsed.header.payload_crc32
+                "Cannot read a single sample from a {} tile without decompressing it.",
+                header.compression.as_str()
             ),
         ));
     }
-
-    let expected_uncompressed_len =
-        expected_payload_length(parsed.header.dimensions, parsed.header.dtype)?;
-    if payload.len() != expected_uncompressed_len {
+    if header.sparse {
         return Err(TileError::new(
-            TileErrorCode::InvalidPayloadLength,
-            format!(
-                "Decoded payload length mismatch. expected={expected_uncompressed_len} got={}",
-                payload.len()
-            ),
+            TileErrorCode::InvalidFieldValue,
+            "Cannot read a single sample from a sparse tile without expanding it.",
+        ));
+    }
+    let (rows, cols, bands) = header.shape();
+    if row >= rows || col >= cols || band >= bands {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!("Sample ({row}, {col}, {band}) out of range for {rows}x{cols}x{bands} tile."),
         ));
     }
 
-    Ok(DecodedTile {
-        header: parsed.header,
-        payload,
-    })
+    let value_size = header.dtype.byte_size() as u64;
+    let offset = sample_index(header.dimensions, row, col, band)
+        .and_then(|index| index.checked_mul(value_size))
+        .filter(|offset| {
+            offset
+                .checked_add(value_size)
+                .is_some_and(|end| end <= parsed.compressed_payload_len as u64)
+        })
+        .ok_or_else(|| {
+            TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                "Stored payload is shorter than the dimensions require.",
+            )
+        })?;
+    let start = TILE_FIXED_HEADER_LENGTH + offset as usize;
+    let value_bytes = &bytes[start..start + value_size as usize];
+    read_numeric_value(header.dtype, header.endianness, value_bytes)
+}
+
+/// Decodes both tiles and compares every parsed header field and the decoded payloads,
+/// ignoring the stored header checksum and compressed payload length.
+pub fn tiles_structurally_equal(a: &[u8], b: &[u8]) -> Result<bool> {
+    let normalize = |bytes: &[u8]| -> Result<DecodedTile> {
+        let mut tile = decode_tile_minimal(bytes)?;
+        tile.header.header_crc32 = 0;
+        tile.header.payload_compressed_bytes = 0;
+        Ok(tile)
+    };
+    Ok(normalize(a)? == normalize(b)?)
 }
 
 pub fn decode_payload_values(
@@ -92,6 +415,25 @@ pub fn decode_payload_values(
     payload: &[u8],
     no_data: Option<f64>,
 ) -> Result<Vec<Option<f64>>> {
+    let mut values = Vec::with_capacity(payload.len() / dtype.byte_size());
+    for_each_payload_value(dtype, endianness, payload, |value| {
+        values.push(match no_data {
+            Some(marker) if is_no_data(value, marker) => None,
+            _ => Some(value),
+        });
+        Ok(())
+    })?;
+    Ok(values)
+}
+
+/// Calls `f` with every sample in payload order without collecting them, stopping at the
+/// first error. No_data samples are passed through as their raw value.
+pub fn for_each_payload_value<F: FnMut(f64) -> Result<()>>(
+    dtype: DType,
+    endianness: Endianness,
+    payload: &[u8],
+    mut f: F,
+) -> Result<()> {
     let value_size = dtype.byte_size();
     if !payload.len().is_multiple_of(value_size) {
         return Err(TileError::new(
@@ -103,71 +445,104 @@ pub fn decode_payload_values(
         ));
     }
 
-    let mut values = Vec::with_capacity(payload.len() / value_size);
     for chunk in payload.chunks_exact(value_size) {
-        let value = read_numeric_value(dtype, endianness, chunk)?;
-        if let Some(marker) = no_data {
-            if value.to_bits() == marker.to_bits() {
-                values.push(None);
-                continue;
-            }
-        }
-        values.push(Some(value));
+        f(read_numeric_value(dtype, endianness, chunk)?)?;
     }
-    Ok(values)
+    Ok(())
 }
 
-fn parse_header(bytes: &[u8]) -> Result<ParsedHeader> {
-    if bytes.len() < TILE_FIXED_HEADER_LENGTH {
-        return Err(TileError::new(
-            TileErrorCode::InvalidHeaderLength,
-            "File shorter than fixed header.",
-        ));
-    }
+/// Reads the fields needed for an integrity manifest after checking magic, version, and
+/// the header checksum. Enum, dimension, and no_data fields are not parsed or validated.
+pub fn read_integrity_fields(bytes: &[u8]) -> Result<IntegrityFields> {
+    verify_header_envelope(bytes, true)?;
+    Ok(IntegrityFields {
+        tile_id: read_u64_le(bytes, OFFSET_TILE_ID)?,
+        payload_crc32: read_u32_le(bytes, OFFSET_PAYLOAD_CHECKSUM)?,
+        uncompressed_bytes: read_u64_le(bytes, OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH)?,
+        compressed_bytes: read_u64_le(bytes, OFFSET_COMPRESSED_PAYLOAD_LENGTH)?,
+    })
+}
 
-    if bytes[0..4] != MAGIC {
-        return Err(TileError::new(
-            TileErrorCode::InvalidMagic,
-            "Invalid file magic.",
-        ));
-    }
+pub(crate) fn parse_header(bytes: &[u8]) -> Result<ParsedHeader> {
+    parse_header_with(bytes, &DecodeOptions::default())
+}
 
-    let format_major = bytes[OFFSET_FORMAT_MAJOR];
-    if format_major != TILE_VERSION_MAJOR {
-        return Err(TileError::new(
-            TileErrorCode::UnsupportedVersion,
-            format!("Unsupported major version {format_major}."),
-        ));
+fn parse_header_with(bytes: &[u8], options: &DecodeOptions) -> Result<ParsedHeader> {
+    let parsed = parse_fixed_header(bytes, options.verify_header_crc32)?;
+    if let Some(max) = options.max_compressed_bytes {
+        let declared = parsed.header.payload_compressed_bytes;
+        if declared > max {
+            return Err(TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                format!(
+                    "Declared compressed payload of {declared} bytes exceeds max_compressed_bytes={max}."
+                ),
+            ));
+        }
     }
+    parse_extensions_into(parsed, bytes)
+}
 
-    let expected_header_crc32 = read_u32_le(bytes, HEADER_CHECKSUM_OFFSET)?;
-    let actual_header_crc32 = crc32(&bytes[..HEADER_CHECKSUM_INPUT_LENGTH]);
-    if expected_header_crc32 != actual_header_crc32 {
-        return Err(TileError::new(
-            TileErrorCode::HeaderChecksumMismatch,
-            format!(
-                "Header checksum mismatch. expected={expected_header_crc32:08x} actual={actual_header_crc32:08x}"
-            ),
-        ));
+// Fills the trailer fields of a fixed header parsed from `bytes`.
+fn parse_extensions_into(mut parsed: ParsedHeader, bytes: &[u8]) -> Result<ParsedHeader> {
+    let payload_end = TILE_FIXED_HEADER_LENGTH + parsed.compressed_payload_len;
+    let trailer = bytes.get(payload_end..).ok_or_else(|| {
+        TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            "File shorter than declared compressed payload length.",
+        )
+    })?;
+    let (extensions, extension_len) = parse_extensions(trailer)?;
+
+    parsed.header.sparse = extensions.sparse;
+    parsed.header.metadata = extensions.metadata;
+    parsed.header.geotransform = extensions.geotransform;
+    if let Some(band_crc32) = &extensions.band_crc32 {
+        if band_crc32.len() != usize::from(parsed.header.dimensions.bands) {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "Band CRC extension has {} entries for {} bands.",
+                    band_crc32.len(),
+                    parsed.header.dimensions.bands
+                ),
+            ));
+        }
     }
+    parsed.header.band_crc32 = extensions.band_crc32;
+    parsed.header.format_minor = extensions.format_minor;
+    parsed.header.payload_adler32 = extensions.payload_adler32;
+    validate_palette(
+        extensions.palette.as_deref(),
+        parsed.header.dtype,
+        parsed.header.dimensions.bands,
+    )?;
+    parsed.header.palette = extensions.palette;
+    parsed.extension_len = extension_len;
+    Ok(parsed)
+}
+
+// Validates the fixed header only; trailer fields are left at their defaults.
+fn parse_fixed_header(bytes: &[u8], verify_header_crc32: bool) -> Result<ParsedHeader> {
+    let (format_major, expected_header_crc32) = verify_header_envelope(bytes, verify_header_crc32)?;
 
     let tile_id = read_u64_le(bytes, OFFSET_TILE_ID)?;
-    let mesh_kind = MeshKind::from_code(bytes[OFFSET_MESH_KIND])?;
+    let mesh_kind = MeshKind::from_code(read_u8(bytes, OFFSET_MESH_KIND)?)?;
     validate_tile_id_for_mesh_kind(tile_id, mesh_kind)?;
 
-    let (dtype, endianness) = unpack_dtype_endian(bytes[OFFSET_DTYPE_ENDIAN])?;
-    let compression = CompressionMode::from_code(bytes[OFFSET_COMPRESSION])?;
+    let (dtype, endianness) = unpack_dtype_endian(read_u8(bytes, OFFSET_DTYPE_ENDIAN)?)?;
+    let compression = CompressionMode::from_code(read_u8(bytes, OFFSET_COMPRESSION)?)?;
 
     let dimensions = TileDimensions {
         rows: read_u32_le(bytes, OFFSET_ROWS)?,
         cols: read_u32_le(bytes, OFFSET_COLS)?,
-        bands: bytes[OFFSET_BANDS],
+        bands: read_u8(bytes, OFFSET_BANDS)?,
     };
     dimensions.validate()?;
 
-    let no_data_kind = bytes[OFFSET_NO_DATA_KIND];
-    let mut no_data_value_raw = [0_u8; 8];
-    no_data_value_raw.copy_from_slice(&bytes[OFFSET_NO_DATA_VALUE..OFFSET_NO_DATA_VALUE + 8]);
+    let no_data_kind = read_u8(bytes, OFFSET_NO_DATA_KIND)?;
+    validate_no_data_kind(no_data_kind)?;
+    let no_data_value_raw = read_bytes::<8>(bytes, OFFSET_NO_DATA_VALUE)?;
     let no_data = decode_no_data_field(no_data_kind, no_data_value_raw, dtype, endianness)?;
 
     let uncompressed_payload_u64 = read_u64_le(bytes, OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH)?;
@@ -186,25 +561,24 @@ fn parse_header(bytes: &[u8]) -> Result<ParsedHeader> {
             "compressed payload length exceeds platform usize.",
         )
     })?;
-
-    let payload_end = TILE_FIXED_HEADER_LENGTH
-        .checked_add(compressed_payload_len)
-        .ok_or_else(|| {
-            TileError::new(
-                TileErrorCode::InvalidPayloadLength,
-                "Compressed payload length overflow.",
-            )
-        })?;
-
-    if bytes.len() < payload_end {
+    if compressed_payload_len > usize::MAX - TILE_FIXED_HEADER_LENGTH {
         return Err(TileError::new(
             TileErrorCode::InvalidPayloadLength,
-            "File shorter than declared compressed payload length.",
+            "Compressed payload length overflow.",
+        ));
+    }
+    if compression == CompressionMode::None && compressed_payload_u64 != uncompressed_payload_u64 {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "compression=none requires equal payload lengths. compressed={compressed_payload_u64} uncompressed={uncompressed_payload_u64}"
+            ),
         ));
     }
 
     let header = TileHeader {
         format_major,
+        format_minor: 0,
         tile_id,
         mesh_kind,
         dtype,
@@ -218,28 +592,237 @@ fn parse_header(bytes: &[u8]) -> Result<ParsedHeader> {
         payload_compressed_bytes: compressed_payload_u64,
         payload_crc32,
         header_crc32: expected_header_crc32,
+        sparse: false,
+        metadata: Vec::new(),
+        geotransform: None,
+        band_crc32: None,
+        payload_adler32: None,
+        palette: None,
     };
 
     Ok(ParsedHeader {
         header,
         compressed_payload_len,
         uncompressed_payload_len,
+        extension_len: 0,
     })
 }
 
-fn decompress_payload(mode: CompressionMode, payload: &[u8]) -> Result<Vec<u8>> {
-    match mode {
-        CompressionMode::None => Ok(payload.to_vec()),
+// Checks length, magic, version, and (unless skipped) the header checksum. Returns the
+// format major version and the stored header checksum.
+fn verify_header_envelope(bytes: &[u8], verify_crc32: bool) -> Result<(u8, u32)> {
+    if bytes.len() < TILE_FIXED_HEADER_LENGTH {
+        return Err(TileError::new(
+            TileErrorCode::InvalidHeaderLength,
+            "File shorter than fixed header.",
+        ));
+    }
+
+    if read_bytes::<4>(bytes, 0)? != MAGIC {
+        return Err(TileError::new(
+            TileErrorCode::InvalidMagic,
+            "Invalid file magic.",
+        ));
+    }
+
+    let format_major = read_u8(bytes, OFFSET_FORMAT_MAJOR)?;
+    if format_major != TILE_VERSION_MAJOR {
+        return Err(TileError::new(
+            TileErrorCode::UnsupportedVersion,
+            format!("Unsupported major version {format_major}."),
+        ));
+    }
+
+    let expected_header_crc32 = read_u32_le(bytes, HEADER_CHECKSUM_OFFSET)?;
+    if !verify_crc32 {
+        return Ok((format_major, expected_header_crc32));
+    }
+    let actual_header_crc32 =
+        compute_header_crc32(&read_bytes::<TILE_FIXED_HEADER_LENGTH>(bytes, 0)?);
+    if expected_header_crc32 != actual_header_crc32 {
+        return Err(TileError::new(
+            TileErrorCode::HeaderChecksumMismatch,
+            format!(
+                "Header checksum mismatch. expected={expected_header_crc32:08x} actual={actual_header_crc32:08x}"
+            ),
+        ));
+    }
+
+    Ok((format_major, expected_header_crc32))
+}
+
+// Checks the stored payload (before sparse expansion) against the payload CRC-32 and, when
+// present, the Adler-32.
+fn verify_payload_checksums(header: &TileHeader, payload: &[u8]) -> Result<()> {
+    let payload_crc32 = crc32(payload);
+    if payload_crc32 != header.payload_crc32 {
+        return Err(TileError::new(
+            TileErrorCode::PayloadChecksumMismatch,
+            format!(
+                "Payload checksum mismatch. expected={:08x} actual={payload_crc32:08x} {}",
+                header.payload_crc32,
+                describe_corrupt_payload(header.compression, payload)
+            ),
+        ));
+    }
+    if let Some(expected) = header.payload_adler32 {
+        let actual = adler32(payload);
+        if actual != expected {
+            return Err(TileError::new(
+                TileErrorCode::PayloadChecksumMismatch,
+                format!("Payload Adler-32 mismatch. expected={expected:08x} actual={actual:08x}"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// The payload is intact (dense length for the dimensions, matching CRC), so only the
+// stored uncompressed length field is wrong.
+fn only_length_field_is_wrong(
+    parsed: &ParsedHeader,
+    payload: &[u8],
+    options: &DecodeOptions,
+) -> Result<bool> {
+    if !options.trust_payload_crc_over_length || parsed.header.sparse {
+        return Ok(false);
+    }
+    let dense_len = expected_payload_length(parsed.header.dimensions, parsed.header.dtype)?;
+    Ok(payload.len() == dense_len && crc32(payload) == parsed.header.payload_crc32)
+}
+
+impl DecodedTile {
+    /// Whether the stored uncompressed length disagrees with the decoded payload, which
+    /// only decodes with [`DecodeOptions::trust_payload_crc_over_length`]. Re-encode the
+    /// tile to repair the field.
+    pub fn has_wrong_length_field(&self) -> bool {
+        !self.header.sparse && self.header.payload_uncompressed_bytes != self.payload.len() as u64
+    }
+}
+
+// `looks_double_compressed` inflates at most this multiple of the payload size.
+#[cfg(feature = "std")]
+const DOUBLE_COMPRESSION_PROBE_FACTOR: u64 = 16;
+
+#[cfg(feature = "std")]
+impl DecodedTile {
+    /// Achieved compression ratio over the ideal ratio implied by the payload's byte
+    /// entropy ([`estimate_entropy`]): `(payload.len() * entropy / 8) / compressed_bytes`.
+    /// Near `1.0` the codec is close to the order-0 entropy bound. Codecs that exploit
+    /// repetition can beat that bound, so values above `1.0` are possible; uncompressed
+    /// tiles score `entropy / 8`. Returns `None` when the header records no compressed
+    /// bytes, as in a hand-built header.
+    pub fn compression_efficiency(&self) -> Option<f64> {
+        if self.header.payload_compressed_bytes == 0 {
+            return None;
+        }
+        let ideal_bytes = self.payload.len() as f64 * estimate_entropy(&self.payload) / 8.0;
+        Some(ideal_bytes / self.header.payload_compressed_bytes as f64)
+    }
+
+    /// Best-effort check for a payload that is itself a compressed stream, as produced by
+    /// encoding already-compressed bytes. Looks for a raw deflate or zlib stream that
+    /// inflates cleanly over the whole payload, or a gzip signature, combined with
+    /// high byte entropy. Inflating stops at 16 times the payload size, so a stream that
+    /// expands further is reported without being inflated in full. Both false positives
+    /// and false negatives are possible, so use it to flag tiles for review rather than to
+    /// reject them.
+    pub fn looks_double_compressed(&self) -> bool {
+        let payload = &self.payload;
+        if payload.len() < 16 || estimate_entropy(payload) < 4.0 {
+            return false;
+        }
+        if payload.starts_with(&[0x1f, 0x8b, 0x08]) {
+            return true;
+        }
+
+        let payload_len = payload.len() as u64;
+        // A stream that inflates up to the cap without an error is taken as compressed.
+        let cap = payload_len.saturating_mul(DOUBLE_COMPRESSION_PROBE_FACTOR);
+        let mut out = Vec::new();
+        let mut raw = DeflateDecoder::new(&payload[..]);
+        if (&mut raw).take(cap).read_to_end(&mut out).is_ok()
+            && (out.len() as u64 == cap
+                || (raw.total_in() == payload_len && out.len() > payload.len()))
+        {
+            return true;
+        }
+
+        let is_zlib_header = payload[0] & 0x0f == 8
+            && (u16::from(payload[0]) << 8 | u16::from(payload[1])).is_multiple_of(31);
+        if is_zlib_header {
+            let mut zlib = ZlibDecoder::new(&payload[..]);
+            out.clear();
+            return (&mut zlib).take(cap).read_to_end(&mut out).is_ok()
+                && (out.len() as u64 == cap
+                    || (zlib.total_in() == payload_len && out.len() > payload.len()));
+        }
+        false
+    }
+}
+
+fn describe_corrupt_payload(compression: CompressionMode, payload: &[u8]) -> String {
+    let head = payload
+        .iter()
+        .take(16)
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let origin = match compression {
+        CompressionMode::None => "payload stored uncompressed",
         CompressionMode::DeflateRaw => {
-            let mut decoder = DeflateDecoder::new(payload);
-            let mut out = Vec::new();
-            decoder.read_to_end(&mut out).map_err(|err| {
+            "deflate-raw stream decompressed cleanly, so the data was corrupted before compression or the stored checksum is wrong"
+        }
+    };
+    format!(
+        "payload_len={} first_bytes=[{head}] ({origin})",
+        payload.len()
+    )
+}
+
+// Without `std` nothing is inflated, so `scratch` goes unused.
+#[cfg_attr(not(feature = "std"), allow(unused_variables, clippy::ptr_arg))]
+fn decompress_payload<'a>(
+    header: &TileHeader,
+    payload: &'a [u8],
+    limit: usize,
+    scratch: &mut Vec<u8>,
+) -> Result<Cow<'a, [u8]>> {
+    match header.compression {
+        CompressionMode::None => Ok(Cow::Borrowed(payload)),
+        #[cfg(feature = "std")]
+        CompressionMode::DeflateRaw => {
+            let mut decoder = DeflateDecoder::new(payload).take((limit as u64).saturating_add(1));
+            scratch.clear();
+            decoder.read_to_end(scratch).map_err(|err| {
+                // A producer bug we have seen: raw samples stored under a deflate-raw flag.
+                // They still match the stored length and checksum as-is.
+                let stored_raw = payload.len() as u64 == header.payload_uncompressed_bytes
+                    && crc32(payload) == header.payload_crc32;
+                let hint = if stored_raw {
+                    "; the stored bytes match the uncompressed length and payload checksum, so the payload is likely uncompressed and the compression field is wrong"
+                } else {
+                    ""
+                };
                 TileError::new(
                     TileErrorCode::DecompressionFailed,
-                    format!("Could not decompress payload using deflate-raw: {err}"),
+                    format!("Could not decompress payload using deflate-raw: {err}{hint}"),
                 )
             })?;
-            Ok(out)
+            if scratch.len() > limit {
+                return Err(TileError::new(
+                    TileErrorCode::InvalidPayloadLength,
+                    format!(
+                        "deflate-raw payload inflates past {limit} bytes, the most the header allows."
+                    ),
+                ));
+            }
+            Ok(Cow::Owned(core::mem::take(scratch)))
         }
+        #[cfg(not(feature = "std"))]
+        CompressionMode::DeflateRaw => Err(TileError::new(
+            TileErrorCode::UnsupportedCompression,
+            "deflate-raw decompression requires the `std` feature.",
+        )),
     }
 }
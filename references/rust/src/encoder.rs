@@ -1,11 +1,17 @@
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io::Write;
 
-use crc32fast::hash as crc32;
+#[cfg(feature = "std")]
 use flate2::write::DeflateEncoder;
 
+use crate::checksum::{adler32, band_crc32s, crc32, Crc32Hasher};
 use crate::common::{
-    encode_no_data_field, expected_payload_length, pack_dtype_endian,
-    validate_tile_id_for_mesh_kind, write_numeric_value,
+    encode_no_data_field, expected_payload_length, pack_dtype_endian, validate_integer_range,
+    validate_tile_id_for_mesh_kind, write_numeric_value, INT24_MAX, INT24_MIN, UINT24_MAX,
 };
 use crate::consts::{
     HEADER_CHECKSUM_INPUT_LENGTH, HEADER_CHECKSUM_OFFSET, MAGIC, OFFSET_BANDS, OFFSET_COLS,
@@ -14,32 +20,429 @@ use crate::consts::{
     OFFSET_ROWS, OFFSET_TILE_ID, OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH, TILE_FIXED_HEADER_LENGTH,
     TILE_VERSION_MAJOR,
 };
+use crate::decoder::parse_header;
+use crate::extension::{
+    encode_extensions, rewrite_extension_record, validate_geotransform, validate_metadata,
+    validate_palette, Extensions, TAG_BAND_CRC32, TAG_PAYLOAD_ADLER32,
+};
+use crate::sparse::sparsify_payload;
 use crate::{
-    CompressionMode, DType, EncodedTile, Endianness, Result, TileEncodeInput, TileError,
-    TileErrorCode, TileHeader,
+    CastPolicy, CompressionMode, DType, EncodeOptions, EncodedTile, Endianness, Result,
+    TileDimensions, TileEncodeInput, TileError, TileErrorCode, TileHeader,
 };
+#[cfg(feature = "std")]
+use crate::{CompressionReport, DeflateStrategy};
+
+#[derive(Debug, Clone, Default)]
+pub struct PayloadBuilder {
+    bytes: Vec<u8>,
+    hasher: Crc32Hasher,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrehashedPayload {
+    pub bytes: Vec<u8>,
+    pub crc32: u32,
+}
+
+impl PayloadBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bytes: Vec::with_capacity(capacity),
+            hasher: Crc32Hasher::new(),
+        }
+    }
+
+    pub fn push_chunk(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        self.bytes.extend_from_slice(chunk);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn finish(self) -> PrehashedPayload {
+        PrehashedPayload {
+            bytes: self.bytes,
+            crc32: self.hasher.finalize(),
+        }
+    }
+}
+
+impl TileEncodeInput<'_> {
+    /// Runs every check `encode_tile` performs before compressing: dimensions, tile_id
+    /// for the mesh kind, payload length, no_data, sparse requirements, metadata size, and
+    /// geotransform values.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_with_payload_len(self.payload.len())
+    }
+
+    // `payload_len` is the uncompressed payload length, which differs from
+    // `self.payload.len()` when the payload is already compressed.
+    fn validate_with_payload_len(&self, payload_len: usize) -> Result<()> {
+        self.dimensions.validate()?;
+        validate_tile_id_for_mesh_kind(self.tile_id, self.mesh_kind)?;
+
+        let expected_payload_len = expected_payload_length(self.dimensions, self.dtype)?;
+        if payload_len != expected_payload_len {
+            return Err(TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                format!(
+                    "Payload byte length mismatch. expected {}x{}x{} {} = {expected_payload_len} bytes, got {}",
+                    self.dimensions.rows,
+                    self.dimensions.cols,
+                    self.dimensions.bands,
+                    self.dtype.as_str(),
+                    payload_len
+                ),
+            ));
+        }
+
+        encode_no_data_field(self.no_data, self.dtype, self.endianness)?;
+        if self.extensions.sparse && self.no_data.is_none() {
+            return Err(TileError::new(
+                TileErrorCode::MissingRequiredField,
+                "Sparse encoding requires no_data to be set.",
+            ));
+        }
+        validate_metadata(self.extensions.metadata)?;
+        validate_palette(self.extensions.palette, self.dtype, self.dimensions.bands)?;
+        validate_geotransform(self.extensions.geotransform)
+    }
+}
 
 pub fn encode_tile(input: TileEncodeInput<'_>) -> Result<EncodedTile> {
-    input.dimensions.validate()?;
-    validate_tile_id_for_mesh_kind(input.tile_id, input.mesh_kind)?;
+    encode_tile_with_options(input, &EncodeOptions::default())
+}
 
-    let expected_payload_len = expected_payload_length(input.dimensions, input.dtype)?;
-    if input.payload.len() != expected_payload_len {
+/// Encodes a tile with explicit encoder settings; see [`EncodeOptions`].
+pub fn encode_tile_with_options(
+    input: TileEncodeInput<'_>,
+    options: &EncodeOptions,
+) -> Result<EncodedTile> {
+    encode_tile_with_payload_crc32(input, None, options)
+}
+
+/// Encodes a tile using a caller-provided CRC-32 of the uncompressed payload.
+///
+/// The CRC is written as-is and is not recomputed. The caller is responsible for its
+/// correctness (e.g. by using [`PayloadBuilder`]); a wrong value produces a tile that
+/// fails decoding with `PayloadChecksumMismatch`.
+pub fn encode_tile_prehashed(
+    input: TileEncodeInput<'_>,
+    payload_crc32: u32,
+) -> Result<EncodedTile> {
+    encode_tile_with_payload_crc32(input, Some(payload_crc32), &EncodeOptions::default())
+}
+
+/// Wraps an already-compressed payload in a tile without decompressing it.
+///
+/// `input.payload` holds the stored bytes for `input.compression`, and `payload_crc32` and
+/// `uncompressed_len` describe the uncompressed payload. Header fields are validated as in
+/// [`encode_tile`], and `uncompressed_len` must match the dimensions and dtype, but the
+/// stored bytes are written as-is: the caller owns the correctness of the stream, its
+/// CRC, and its length, and a mismatch only surfaces when the tile is decoded. Sparse
+/// encoding, band CRCs, and Adler-32 need the uncompressed payload and are rejected.
+pub fn encode_tile_precompressed(
+    input: TileEncodeInput<'_>,
+    payload_crc32: u32,
+    uncompressed_len: usize,
+) -> Result<EncodedTile> {
+    encode_tile_precompressed_with_options(
+        input,
+        payload_crc32,
+        uncompressed_len,
+        &EncodeOptions::default(),
+    )
+}
+
+/// Like [`encode_tile_precompressed`], with explicit encoder settings. Only
+/// [`EncodeOptions::format_minor`] applies, since the payload is not recompressed.
+pub fn encode_tile_precompressed_with_options(
+    input: TileEncodeInput<'_>,
+    payload_crc32: u32,
+    uncompressed_len: usize,
+    options: &EncodeOptions,
+) -> Result<EncodedTile> {
+    input.validate_with_payload_len(uncompressed_len)?;
+    if input.extensions.sparse || input.extensions.band_crc32 || input.extensions.payload_adler32 {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            "Sparse encoding, band CRCs, and Adler-32 need an uncompressed payload.",
+        ));
+    }
+    if input.compression == CompressionMode::None && input.payload.len() != uncompressed_len {
         return Err(TileError::new(
             TileErrorCode::InvalidPayloadLength,
             format!(
-                "Payload byte length mismatch. expected={expected_payload_len} got={}",
+                "Uncompressed tiles must store {uncompressed_len} payload bytes, got {}.",
                 input.payload.len()
             ),
         ));
     }
 
-    let payload_crc32 = crc32(input.payload);
-    let compressed_payload = compress_payload(input.compression, input.payload)?;
-    let compressed_payload_len = compressed_payload.len();
+    let extensions = Extensions {
+        metadata: input.extensions.metadata.to_vec(),
+        geotransform: input.extensions.geotransform,
+        format_minor: options.format_minor,
+        palette: input.extensions.palette.map(<[_]>::to_vec),
+        ..Extensions::default()
+    };
+    let extension_bytes = encode_extensions(&extensions)?;
+    let (header_bytes, header) = build_header(
+        &input,
+        uncompressed_len,
+        input.payload.len(),
+        payload_crc32,
+        &extensions,
+    )?;
+
+    let mut bytes = Vec::with_capacity(tile_length(input.payload.len(), extension_bytes.len())?);
+    bytes.extend_from_slice(&header_bytes);
+    bytes.extend_from_slice(input.payload);
+    bytes.extend_from_slice(&extension_bytes);
+    Ok(EncodedTile { bytes, header })
+}
 
+fn encode_tile_with_payload_crc32(
+    input: TileEncodeInput<'_>,
+    payload_crc32: Option<u32>,
+    options: &EncodeOptions,
+) -> Result<EncodedTile> {
+    let prepared = prepare_tile(&input, payload_crc32, options)?;
+
+    let mut bytes = Vec::with_capacity(tile_length(
+        prepared.stored_payload.len(),
+        prepared.extension_bytes.len(),
+    )?);
+    bytes.extend_from_slice(&prepared.header_bytes);
+    bytes.extend_from_slice(&prepared.stored_payload);
+    bytes.extend_from_slice(&prepared.extension_bytes);
+    check_emitted_payload_len(
+        &prepared.header,
+        bytes.len() - TILE_FIXED_HEADER_LENGTH - prepared.extension_bytes.len(),
+    )?;
+
+    Ok(EncodedTile {
+        bytes,
+        header: prepared.header,
+    })
+}
+
+/// Encodes a tile straight to `writer` and returns its header.
+///
+/// With `CompressionMode::None` the input payload is written as-is after the header, so
+/// no encoded copy of the tile is held in memory. Compressed modes still buffer the
+/// compressed payload, because the header records its length and is written first.
+#[cfg(feature = "std")]
+pub fn encode_tile_to_writer<W: Write>(
+    writer: &mut W,
+    input: TileEncodeInput<'_>,
+) -> Result<TileHeader> {
+    let prepared = prepare_tile(&input, None, &EncodeOptions::default())?;
+    let mut write = |part: &[u8]| {
+        writer.write_all(part).map_err(|err| {
+            TileError::new(
+                TileErrorCode::IoError,
+                format!("Could not write encoded tile: {err}"),
+            )
+        })?;
+        Ok::<usize, TileError>(part.len())
+    };
+    write(&prepared.header_bytes)?;
+    let payload_written = write(&prepared.stored_payload)?;
+    check_emitted_payload_len(&prepared.header, payload_written)?;
+    write(&prepared.extension_bytes)?;
+    Ok(prepared.header)
+}
+
+// Above this many bits per byte, deflate rarely saves enough to be worth the CPU.
+#[cfg(feature = "std")]
+const COMPRESSIBLE_ENTROPY_LIMIT: f64 = 7.5;
+
+/// Shannon entropy of the byte histogram in bits per byte, from `0.0` (one repeated byte
+/// or empty) to `8.0` (uniformly distributed bytes).
+#[cfg(feature = "std")]
+pub fn estimate_entropy(payload: &[u8]) -> f64 {
+    let mut counts = [0_usize; 256];
+    for &byte in payload {
+        counts[usize::from(byte)] += 1;
+    }
+    let len = payload.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Cheap pre-check before compressing: `false` when the payload's byte entropy is above
+/// 7.5 bits per byte, where deflate-raw is unlikely to shrink it. The histogram ignores
+/// byte order, so a `true` result is a hint rather than a guarantee.
+#[cfg(feature = "std")]
+pub fn is_likely_compressible(payload: &[u8]) -> bool {
+    estimate_entropy(payload) <= COMPRESSIBLE_ENTROPY_LIMIT
+}
+
+/// Compresses `payload` once with each mode and reports the stored size and the time
+/// spent compressing, without building a tile. Reports follow the order of `modes`.
+#[cfg(feature = "std")]
+pub fn evaluate_compression(
+    payload: &[u8],
+    modes: &[CompressionMode],
+) -> Result<Vec<CompressionReport>> {
+    modes
+        .iter()
+        .map(|&mode| {
+            let started = std::time::Instant::now();
+            let compressed =
+                compress_payload(mode, Cow::Borrowed(payload), &EncodeOptions::default())?;
+            let elapsed = started.elapsed();
+            Ok(CompressionReport {
+                mode,
+                compressed_bytes: compressed.len(),
+                encode_nanos: u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+            })
+        })
+        .collect()
+}
+
+// The header is written before (or independently of) the payload, so a length that
+// disagrees with the emitted bytes would only surface later as a confusing decode error.
+fn check_emitted_payload_len(header: &TileHeader, emitted: usize) -> Result<()> {
+    debug_assert_eq!(
+        header.payload_compressed_bytes, emitted as u64,
+        "compressed_payload_length disagrees with emitted payload bytes"
+    );
+    if header.payload_compressed_bytes != emitted as u64 {
+        return Err(TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!(
+                "Header declares compressed_payload_length={} but {emitted} payload bytes were emitted.",
+                header.payload_compressed_bytes
+            ),
+        ));
+    }
+    Ok(())
+}
+
+struct PreparedTile<'a> {
+    header_bytes: [u8; TILE_FIXED_HEADER_LENGTH],
+    header: TileHeader,
+    // Borrows the input payload when it is stored uncompressed and dense.
+    stored_payload: Cow<'a, [u8]>,
+    extension_bytes: Vec<u8>,
+}
+
+fn prepare_tile<'a>(
+    input: &TileEncodeInput<'a>,
+    payload_crc32: Option<u32>,
+    options: &EncodeOptions,
+) -> Result<PreparedTile<'a>> {
+    input.validate()?;
+    if options.compression_level > 9 {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "compression_level must be 0..=9, got {}.",
+                options.compression_level
+            ),
+        ));
+    }
+
+    let mut extensions = Extensions {
+        metadata: input.extensions.metadata.to_vec(),
+        geotransform: input.extensions.geotransform,
+        band_crc32: input.extensions.band_crc32.then(|| {
+            let bands: Vec<u8> = (0..input.dimensions.bands).collect();
+            band_crc32s(
+                input.payload,
+                input.dtype.byte_size(),
+                input.dimensions.bands,
+                &bands,
+            )
+        }),
+        format_minor: options.format_minor,
+        palette: input.extensions.palette.map(<[_]>::to_vec),
+        ..Extensions::default()
+    };
+    let sparse_payload = match input.no_data {
+        Some(no_data) if input.extensions.sparse => {
+            sparsify_payload(input.payload, input.dtype, input.endianness, no_data)?
+        }
+        _ => None,
+    };
+    let (payload, payload_crc32) = match sparse_payload {
+        Some(sparse) => {
+            if payload_crc32.is_some() {
+                return Err(TileError::new(
+                    TileErrorCode::InvalidFieldValue,
+                    "A precomputed payload CRC cannot be used with sparse encoding.",
+                ));
+            }
+            extensions.sparse = true;
+            let sparse_crc32 = crc32(&sparse);
+            (Cow::Owned(sparse), sparse_crc32)
+        }
+        None => (
+            Cow::Borrowed(input.payload),
+            payload_crc32.unwrap_or_else(|| crc32(input.payload)),
+        ),
+    };
+
+    if input.extensions.payload_adler32 {
+        extensions.payload_adler32 = Some(adler32(&payload));
+    }
+
+    let uncompressed_payload_len = payload.len();
+    let (compression, stored_payload) = compress_with_options(input.compression, payload, options)?;
+    let input = &TileEncodeInput {
+        compression,
+        ..input.clone()
+    };
+    let extension_bytes = encode_extensions(&extensions)?;
+
+    let (header_bytes, header) = build_header(
+        input,
+        uncompressed_payload_len,
+        stored_payload.len(),
+        payload_crc32,
+        &extensions,
+    )?;
+
+    Ok(PreparedTile {
+        header_bytes,
+        header,
+        stored_payload,
+        extension_bytes,
+    })
+}
+
+pub(crate) fn build_header(
+    input: &TileEncodeInput<'_>,
+    uncompressed_payload_len: usize,
+    compressed_payload_len: usize,
+    payload_crc32: u32,
+    extensions: &Extensions,
+) -> Result<([u8; TILE_FIXED_HEADER_LENGTH], TileHeader)> {
     let (no_data_kind, no_data_value_raw) =
         encode_no_data_field(input.no_data, input.dtype, input.endianness)?;
+    // Decoders address the payload as `TILE_FIXED_HEADER_LENGTH + compressed length`.
+    tile_length(compressed_payload_len, 0)?;
+    let uncompressed_payload_u64 = length_field(uncompressed_payload_len)?;
+    let compressed_payload_u64 = length_field(compressed_payload_len)?;
 
     let mut header_bytes = [0_u8; TILE_FIXED_HEADER_LENGTH];
     header_bytes[0..4].copy_from_slice(&MAGIC);
@@ -57,24 +460,16 @@ pub fn encode_tile(input: TileEncodeInput<'_>) -> Result<EncodedTile> {
     header_bytes[OFFSET_NO_DATA_VALUE..OFFSET_NO_DATA_VALUE + 8]
         .copy_from_slice(&no_data_value_raw);
     header_bytes[OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH..OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH + 8]
-        .copy_from_slice(&(input.payload.len() as u64).to_le_bytes());
+        .copy_from_slice(&uncompressed_payload_u64.to_le_bytes());
     header_bytes[OFFSET_COMPRESSED_PAYLOAD_LENGTH..OFFSET_COMPRESSED_PAYLOAD_LENGTH + 8]
-        .copy_from_slice(&(compressed_payload_len as u64).to_le_bytes());
+        .copy_from_slice(&compressed_payload_u64.to_le_bytes());
     header_bytes[OFFSET_PAYLOAD_CHECKSUM..OFFSET_PAYLOAD_CHECKSUM + 4]
         .copy_from_slice(&payload_crc32.to_le_bytes());
-    header_bytes[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
-        .copy_from_slice(&0_u32.to_le_bytes());
-
-    let header_crc32 = crc32(&header_bytes[..HEADER_CHECKSUM_INPUT_LENGTH]);
-    header_bytes[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
-        .copy_from_slice(&header_crc32.to_le_bytes());
-
-    let mut bytes = Vec::with_capacity(TILE_FIXED_HEADER_LENGTH + compressed_payload_len);
-    bytes.extend_from_slice(&header_bytes);
-    bytes.extend_from_slice(&compressed_payload);
+    let header_crc32 = seal_header(&mut header_bytes);
 
     let header = TileHeader {
         format_major: TILE_VERSION_MAJOR,
+        format_minor: extensions.format_minor,
         tile_id: input.tile_id,
         mesh_kind: input.mesh_kind,
         dtype: input.dtype,
@@ -84,13 +479,136 @@ pub fn encode_tile(input: TileEncodeInput<'_>) -> Result<EncodedTile> {
         no_data_kind,
         no_data_value_raw,
         no_data: input.no_data,
-        payload_uncompressed_bytes: input.payload.len() as u64,
-        payload_compressed_bytes: compressed_payload_len as u64,
+        payload_uncompressed_bytes: uncompressed_payload_u64,
+        payload_compressed_bytes: compressed_payload_u64,
         payload_crc32,
         header_crc32,
+        sparse: extensions.sparse,
+        metadata: extensions.metadata.clone(),
+        geotransform: extensions.geotransform,
+        band_crc32: extensions.band_crc32.clone(),
+        payload_adler32: extensions.payload_adler32,
+        palette: extensions.palette.clone(),
     };
 
-    Ok(EncodedTile { bytes, header })
+    Ok((header_bytes, header))
+}
+
+/// Recomputes the payload CRC of an uncompressed tile after its payload bytes were edited
+/// in place, and rewrites the payload and header checksums, plus any payload Adler-32 or
+/// per-band CRC records and the trailer checksum. Returns the new payload CRC.
+///
+/// The header must still be valid. Compressed tiles return `UnsupportedCompression`,
+/// since their stored bytes cannot be meaningfully edited in place. Sparse tiles with band
+/// CRCs are rejected, since those CRCs cover the expanded payload.
+pub fn refresh_payload_checksum(bytes: &mut [u8]) -> Result<u32> {
+    let parsed = parse_header(bytes)?;
+    if parsed.header.compression != CompressionMode::None {
+        return Err(TileError::new(
+            TileErrorCode::UnsupportedCompression,
+            format!(
+                "Cannot refresh the payload checksum of a {} tile in place.",
+                parsed.header.compression.as_str()
+            ),
+        ));
+    }
+    if parsed.header.sparse && parsed.header.band_crc32.is_some() {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            "Cannot refresh band CRCs in place: they cover the dense payload of a sparse tile.",
+        ));
+    }
+
+    let payload_end = tile_length(parsed.compressed_payload_len, 0)?;
+    let (head, trailer) = bytes.split_at_mut(payload_end);
+    let payload = &head[TILE_FIXED_HEADER_LENGTH..];
+    let payload_crc32 = crc32(payload);
+    if parsed.header.payload_adler32.is_some() {
+        let adler = adler32(payload).to_le_bytes();
+        rewrite_extension_record(trailer, TAG_PAYLOAD_ADLER32, &adler)?;
+    }
+    if parsed.header.band_crc32.is_some() {
+        let bands: Vec<u8> = (0..parsed.header.dimensions.bands).collect();
+        let value: Vec<u8> = band_crc32s(
+            payload,
+            parsed.header.dtype.byte_size(),
+            parsed.header.dimensions.bands,
+            &bands,
+        )
+        .iter()
+        .flat_map(|crc| crc.to_le_bytes())
+        .collect();
+        rewrite_extension_record(trailer, TAG_BAND_CRC32, &value)?;
+    }
+    bytes[OFFSET_PAYLOAD_CHECKSUM..OFFSET_PAYLOAD_CHECKSUM + 4]
+        .copy_from_slice(&payload_crc32.to_le_bytes());
+    seal_header(&mut bytes[..TILE_FIXED_HEADER_LENGTH]);
+    Ok(payload_crc32)
+}
+
+/// Relabels a tile's dtype in place without touching the payload, e.g. to repair a uint16
+/// tile mislabeled as int16. Only the dtype code and header CRC change; the endianness,
+/// payload bytes, and payload CRC are kept, so samples and the no_data marker are
+/// reinterpreted, not converted.
+///
+/// `new_dtype` must have the same byte width as the current dtype. The tile must still
+/// parse afterwards (for example, a NaN no_data marker cannot move to an integer dtype);
+/// otherwise the bytes are left unchanged and the parse error is returned.
+pub fn reinterpret_dtype(bytes: &mut [u8], new_dtype: DType) -> Result<()> {
+    let header = parse_header(bytes)?.header;
+    if new_dtype.byte_size() != header.dtype.byte_size() {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "Cannot reinterpret {} as {}: sample widths differ ({} vs {} bytes).",
+                header.dtype.as_str(),
+                new_dtype.as_str(),
+                header.dtype.byte_size(),
+                new_dtype.byte_size()
+            ),
+        ));
+    }
+
+    let original: [u8; TILE_FIXED_HEADER_LENGTH] = bytes[..TILE_FIXED_HEADER_LENGTH]
+        .try_into()
+        .expect("parsed header length");
+    bytes[OFFSET_DTYPE_ENDIAN] = pack_dtype_endian(new_dtype, header.endianness);
+    seal_header(&mut bytes[..TILE_FIXED_HEADER_LENGTH]);
+    if let Err(err) = parse_header(bytes) {
+        bytes[..TILE_FIXED_HEADER_LENGTH].copy_from_slice(&original);
+        return Err(err);
+    }
+    Ok(())
+}
+
+// Writes the header checksum over the other header fields and returns it.
+fn seal_header(header_bytes: &mut [u8]) -> u32 {
+    let header_crc32 = crc32(&header_bytes[..HEADER_CHECKSUM_INPUT_LENGTH]);
+    header_bytes[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+        .copy_from_slice(&header_crc32.to_le_bytes());
+    header_crc32
+}
+
+/// Total encoded tile length, rejecting lengths that overflow `usize`.
+pub(crate) fn tile_length(compressed_payload_len: usize, extension_len: usize) -> Result<usize> {
+    TILE_FIXED_HEADER_LENGTH
+        .checked_add(compressed_payload_len)
+        .and_then(|len| len.checked_add(extension_len))
+        .ok_or_else(|| {
+            TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                "Encoded tile length overflows usize.",
+            )
+        })
+}
+
+fn length_field(len: usize) -> Result<u64> {
+    u64::try_from(len).map_err(|_| {
+        TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!("Payload length {len} does not fit the u64 header field."),
+        )
+    })
 }
 
 pub fn encode_payload_values(
@@ -98,6 +616,12 @@ pub fn encode_payload_values(
     endianness: Endianness,
     values: &[f64],
 ) -> Result<Vec<u8>> {
+    if cfg!(target_endian = "little") && endianness == Endianness::Little {
+        if let Some(out) = encode_native_integer_values(dtype, values)? {
+            return Ok(out);
+        }
+    }
+
     let value_size = dtype.byte_size();
     let mut out = vec![0_u8; values.len() * value_size];
 
@@ -110,23 +634,242 @@ pub fn encode_payload_values(
     Ok(out)
 }
 
-fn compress_payload(mode: CompressionMode, payload: &[u8]) -> Result<Vec<u8>> {
+/// Like [`encode_payload_values`], but passes each value through [`DType::cast_value`] first.
+pub fn encode_payload_values_with_policy(
+    dtype: DType,
+    endianness: Endianness,
+    values: &[f64],
+    policy: CastPolicy,
+) -> Result<Vec<u8>> {
+    let value_size = dtype.byte_size();
+    let mut out = vec![0_u8; values.len() * value_size];
+
+    for (idx, value) in values.iter().enumerate() {
+        let value = dtype.cast_value(*value, policy)?;
+        let start = idx * value_size;
+        write_numeric_value(
+            dtype,
+            endianness,
+            value,
+            true,
+            &mut out[start..start + value_size],
+        )?;
+    }
+
+    Ok(out)
+}
+
+/// Encodes `data` laid out in the canonical order (spec §5: row-major, bands interleaved)
+/// after checking it holds exactly `rows * cols * bands` samples for `dimensions`.
+pub fn encode_from_shaped<T: Into<f64> + Copy>(
+    dtype: DType,
+    endianness: Endianness,
+    data: &[T],
+    dimensions: TileDimensions,
+) -> Result<Vec<u8>> {
+    dimensions.validate()?;
+    let expected = dimensions.total_samples()?;
+    if data.len() as u64 != expected {
+        return Err(TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!(
+                "Shaped data has {} samples, expected {}x{}x{} = {expected}.",
+                data.len(),
+                dimensions.rows,
+                dimensions.cols,
+                dimensions.bands
+            ),
+        ));
+    }
+    encode_payload_from_iter(
+        dtype,
+        endianness,
+        data.len(),
+        data.iter().map(|&value| value.into()),
+    )
+}
+
+pub fn encode_payload_from_iter<I: IntoIterator<Item = f64>>(
+    dtype: DType,
+    endianness: Endianness,
+    count: usize,
+    iter: I,
+) -> Result<Vec<u8>> {
+    let value_size = dtype.byte_size();
+    let byte_len = count.checked_mul(value_size).ok_or_else(|| {
+        TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            "Payload length overflow.",
+        )
+    })?;
+    let mut out = vec![0_u8; byte_len];
+
+    let mut written = 0_usize;
+    for value in iter {
+        if written == count {
+            return Err(TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                format!("Value iterator yielded more than {count} values."),
+            ));
+        }
+        let start = written * value_size;
+        write_numeric_value(
+            dtype,
+            endianness,
+            value,
+            true,
+            &mut out[start..start + value_size],
+        )?;
+        written += 1;
+    }
+
+    if written != count {
+        return Err(TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!("Value iterator yielded {written} values, expected {count}."),
+        ));
+    }
+    Ok(out)
+}
+
+// Fast path for little-endian hosts writing little-endian integer payloads: native byte
+// order is the target byte order, so each validated value is cast and copied directly.
+fn encode_native_integer_values(dtype: DType, values: &[f64]) -> Result<Option<Vec<u8>>> {
+    let out = match dtype {
+        DType::Uint8 => collect_native(values, 0.0, u8::MAX as f64, |v| (v as u8).to_ne_bytes())?,
+        DType::Int8 => collect_native(values, i8::MIN as f64, i8::MAX as f64, |v| {
+            (v as i8).to_ne_bytes()
+        })?,
+        DType::Uint16 => {
+            collect_native(values, 0.0, u16::MAX as f64, |v| (v as u16).to_ne_bytes())?
+        }
+        DType::Int16 => collect_native(values, i16::MIN as f64, i16::MAX as f64, |v| {
+            (v as i16).to_ne_bytes()
+        })?,
+        DType::Uint32 => {
+            collect_native(values, 0.0, u32::MAX as f64, |v| (v as u32).to_ne_bytes())?
+        }
+        DType::Int32 => collect_native(values, i32::MIN as f64, i32::MAX as f64, |v| {
+            (v as i32).to_ne_bytes()
+        })?,
+        DType::Uint24 => collect_native(values, 0.0, UINT24_MAX, |v| {
+            let [a, b, c, _] = (v as u32).to_ne_bytes();
+            [a, b, c]
+        })?,
+        DType::Int24 => collect_native(values, INT24_MIN, INT24_MAX, |v| {
+            let [a, b, c, _] = (v as i32).to_ne_bytes();
+            [a, b, c]
+        })?,
+        DType::Float32 | DType::Float64 => return Ok(None),
+    };
+    Ok(Some(out))
+}
+
+fn collect_native<const N: usize>(
+    values: &[f64],
+    min: f64,
+    max: f64,
+    to_bytes: impl Fn(f64) -> [u8; N],
+) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(values.len() * N);
+    for value in values {
+        let v = validate_integer_range(*value, min, max)?;
+        out.extend_from_slice(&to_bytes(v));
+    }
+    Ok(out)
+}
+
+// Returns the compression mode actually used, which differs from `mode` only when
+// `compression_auto` stores the payload uncompressed.
+fn compress_with_options<'a>(
+    mode: CompressionMode,
+    payload: Cow<'a, [u8]>,
+    options: &EncodeOptions,
+) -> Result<(CompressionMode, Cow<'a, [u8]>)> {
+    if options.deterministic {
+        return Ok((CompressionMode::None, payload));
+    }
+    if !options.compression_auto || mode == CompressionMode::None {
+        return Ok((mode, compress_payload(mode, payload, options)?));
+    }
+    #[cfg(feature = "std")]
+    if !is_likely_compressible(&payload) {
+        return Ok((CompressionMode::None, payload));
+    }
+    let compressed = compress_payload(mode, Cow::Borrowed(&payload), options)?.into_owned();
+    if compressed.len() >= payload.len() {
+        return Ok((CompressionMode::None, payload));
+    }
+    Ok((mode, Cow::Owned(compressed)))
+}
+
+fn compress_payload<'a>(
+    mode: CompressionMode,
+    payload: Cow<'a, [u8]>,
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))] options: &EncodeOptions,
+) -> Result<Cow<'a, [u8]>> {
     match mode {
-        CompressionMode::None => Ok(payload.to_vec()),
+        CompressionMode::None => Ok(payload),
+        #[cfg(feature = "std")]
+        CompressionMode::DeflateRaw if options.deflate_strategy != DeflateStrategy::Default => {
+            Ok(Cow::Owned(deflate_with_strategy(
+                &payload,
+                options.compression_level,
+                options.deflate_strategy,
+            )?))
+        }
+        #[cfg(feature = "std")]
         CompressionMode::DeflateRaw => {
-            let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::best());
-            encoder.write_all(payload).map_err(|err| {
+            let level = flate2::Compression::new(options.compression_level);
+            let mut encoder = DeflateEncoder::new(Vec::new(), level);
+            encoder.write_all(&payload).map_err(|err| {
                 TileError::new(
                     TileErrorCode::CompressionFailed,
                     format!("Could not compress payload using deflate-raw: {err}"),
                 )
             })?;
-            encoder.finish().map_err(|err| {
+            let compressed = encoder.finish().map_err(|err| {
                 TileError::new(
                     TileErrorCode::CompressionFailed,
                     format!("Could not finish deflate-raw compression: {err}"),
                 )
-            })
+            })?;
+            Ok(Cow::Owned(compressed))
         }
+        #[cfg(not(feature = "std"))]
+        CompressionMode::DeflateRaw => Err(TileError::new(
+            TileErrorCode::UnsupportedCompression,
+            "deflate-raw compression requires the `std` feature.",
+        )),
+    }
+}
+
+#[cfg(feature = "std")]
+fn deflate_with_strategy(payload: &[u8], level: u32, strategy: DeflateStrategy) -> Result<Vec<u8>> {
+    use miniz_oxide::deflate::core::{
+        compress_to_output, create_comp_flags_from_zip_params, CompressionStrategy,
+        CompressorOxide, TDEFLFlush, TDEFLStatus,
+    };
+
+    let strategy = match strategy {
+        DeflateStrategy::Default => CompressionStrategy::Default,
+        DeflateStrategy::Filtered => CompressionStrategy::Filtered,
+        DeflateStrategy::HuffmanOnly => CompressionStrategy::HuffmanOnly,
+        DeflateStrategy::Rle => CompressionStrategy::RLE,
+    };
+    // Negative window bits select a raw deflate stream without the zlib wrapper.
+    let flags = create_comp_flags_from_zip_params(level as i32, -15, strategy as i32);
+    let mut compressor = CompressorOxide::new(flags);
+    let mut compressed = Vec::new();
+    let (status, _) = compress_to_output(&mut compressor, payload, TDEFLFlush::Finish, |chunk| {
+        compressed.extend_from_slice(chunk);
+        true
+    });
+    if status != TDEFLStatus::Done {
+        return Err(TileError::new(
+            TileErrorCode::CompressionFailed,
+            format!("Could not finish deflate-raw compression: {status:?}"),
+        ));
     }
+    Ok(compressed)
 }
@@ -0,0 +1,431 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::checksum::crc32;
+use crate::common::read_u32_le;
+use crate::{DType, Result, TileError, TileErrorCode, TileHeader};
+
+pub(crate) const EXTENSION_MAGIC: [u8; 4] = *b"MTX1";
+// magic + body length + body crc32
+pub(crate) const EXTENSION_FRAMING_LENGTH: usize = 12;
+// Records with this bit set change how the payload is interpreted; readers must reject
+// critical tags they do not understand. Other unknown tags are skipped.
+const CRITICAL_TAG_BIT: u8 = 0x80;
+
+pub(crate) const TAG_METADATA: u8 = 0x01;
+pub(crate) const TAG_GEOTRANSFORM: u8 = 0x02;
+pub(crate) const TAG_BAND_CRC32: u8 = 0x03;
+pub(crate) const TAG_FORMAT_MINOR: u8 = 0x04;
+pub(crate) const TAG_PAYLOAD_ADLER32: u8 = 0x05;
+pub(crate) const TAG_PALETTE: u8 = 0x06;
+pub(crate) const TAG_SPARSE: u8 = 0x81;
+
+pub(crate) const MAX_METADATA_LENGTH: usize = 64 * 1024;
+pub(crate) const MAX_PALETTE_ENTRIES: usize = 256;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Extensions {
+    pub(crate) sparse: bool,
+    pub(crate) metadata: Vec<(String, String)>,
+    pub(crate) geotransform: Option<[f64; 6]>,
+    pub(crate) band_crc32: Option<Vec<u32>>,
+    pub(crate) format_minor: u8,
+    pub(crate) payload_adler32: Option<u32>,
+    pub(crate) palette: Option<Vec<[u8; 4]>>,
+}
+
+impl Extensions {
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.sparse
+            && self.metadata.is_empty()
+            && self.geotransform.is_none()
+            && self.band_crc32.is_none()
+            && self.format_minor == 0
+            && self.payload_adler32.is_none()
+            && self.palette.is_none()
+    }
+}
+
+pub(crate) fn encode_extensions(extensions: &Extensions) -> Result<Vec<u8>> {
+    if extensions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut body = Vec::new();
+    if extensions.format_minor != 0 {
+        push_record(&mut body, TAG_FORMAT_MINOR, &[extensions.format_minor]);
+    }
+    if extensions.sparse {
+        push_record(&mut body, TAG_SPARSE, &[]);
+    }
+    if !extensions.metadata.is_empty() {
+        push_record(
+            &mut body,
+            TAG_METADATA,
+            &encode_metadata(&extensions.metadata)?,
+        );
+    }
+    if let Some(geotransform) = extensions.geotransform {
+        validate_geotransform(Some(geotransform))?;
+        let value: Vec<u8> = geotransform.iter().flat_map(|v| v.to_le_bytes()).collect();
+        push_record(&mut body, TAG_GEOTRANSFORM, &value);
+    }
+    if let Some(band_crc32) = &extensions.band_crc32 {
+        let value: Vec<u8> = band_crc32.iter().flat_map(|v| v.to_le_bytes()).collect();
+        push_record(&mut body, TAG_BAND_CRC32, &value);
+    }
+    if let Some(adler32) = extensions.payload_adler32 {
+        push_record(&mut body, TAG_PAYLOAD_ADLER32, &adler32.to_le_bytes());
+    }
+    if let Some(palette) = &extensions.palette {
+        push_record(&mut body, TAG_PALETTE, palette.as_flattened());
+    }
+
+    let mut out = Vec::with_capacity(EXTENSION_FRAMING_LENGTH + body.len());
+    out.extend_from_slice(&EXTENSION_MAGIC);
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&body);
+    out.extend_from_slice(&crc32(&body).to_le_bytes());
+    Ok(out)
+}
+
+/// Length of the trailer [`encode_extensions`] writes for the trailer fields of `header`,
+/// computed without encoding it; `0` when there is no trailer.
+pub(crate) fn extension_trailer_len(header: &TileHeader) -> u64 {
+    // tag + value length
+    const RECORD_FRAMING_LENGTH: u64 = 5;
+    let mut body_len = 0_u64;
+    let mut record = |value_len: usize| body_len += RECORD_FRAMING_LENGTH + value_len as u64;
+    if header.format_minor != 0 {
+        record(1);
+    }
+    if header.sparse {
+        record(0);
+    }
+    if !header.metadata.is_empty() {
+        record(
+            header
+                .metadata
+                .iter()
+                .map(|(key, value)| 8 + key.len() + value.len())
+                .sum(),
+        );
+    }
+    if header.geotransform.is_some() {
+        record(48);
+    }
+    if let Some(band_crc32) = &header.band_crc32 {
+        record(4 * band_crc32.len());
+    }
+    if header.payload_adler32.is_some() {
+        record(4);
+    }
+    if let Some(palette) = &header.palette {
+        record(4 * palette.len());
+    }
+    if body_len == 0 {
+        0
+    } else {
+        EXTENSION_FRAMING_LENGTH as u64 + body_len
+    }
+}
+
+pub(crate) fn validate_geotransform(geotransform: Option<[f64; 6]>) -> Result<()> {
+    if geotransform.is_some_and(|values| !values.iter().all(|v| v.is_finite())) {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            "Geotransform values must be finite.",
+        ));
+    }
+    Ok(())
+}
+
+fn parse_geotransform(value: &[u8]) -> Result<[f64; 6]> {
+    if value.len() != 48 {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "Geotransform extension record must be 48 bytes, got {}.",
+                value.len()
+            ),
+        ));
+    }
+    let mut geotransform = [0.0; 6];
+    for (slot, chunk) in geotransform.iter_mut().zip(value.chunks_exact(8)) {
+        *slot = f64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+    }
+    validate_geotransform(Some(geotransform))?;
+    Ok(geotransform)
+}
+
+/// Palettes map class codes, so they only apply to single-band integer tiles.
+pub(crate) fn palette_applies(dtype: DType, bands: u8) -> bool {
+    bands == 1 && !matches!(dtype, DType::Float32 | DType::Float64)
+}
+
+pub(crate) fn validate_palette(palette: Option<&[[u8; 4]]>, dtype: DType, bands: u8) -> Result<()> {
+    let Some(palette) = palette else {
+        return Ok(());
+    };
+    if !palette_applies(dtype, bands) {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "A palette requires a single-band integer tile, got {bands} {} bands.",
+                dtype.as_str()
+            ),
+        ));
+    }
+    if palette.is_empty() || palette.len() > MAX_PALETTE_ENTRIES {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "A palette must have 1 to {MAX_PALETTE_ENTRIES} entries, got {}.",
+                palette.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn parse_palette(value: &[u8]) -> Result<Vec<[u8; 4]>> {
+    if !value.len().is_multiple_of(4) {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "Palette extension record must be a multiple of 4 bytes, got {}.",
+                value.len()
+            ),
+        ));
+    }
+    Ok(value
+        .chunks_exact(4)
+        .map(|chunk| chunk.try_into().expect("4-byte chunk"))
+        .collect())
+}
+
+fn parse_band_crc32(value: &[u8]) -> Result<Vec<u32>> {
+    if value.is_empty() || !value.len().is_multiple_of(4) {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!(
+                "Band CRC extension record must be a non-empty multiple of 4 bytes, got {}.",
+                value.len()
+            ),
+        ));
+    }
+    Ok(value
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().expect("4-byte chunk")))
+        .collect())
+}
+
+// Each entry is `u32 key_len || key || u32 value_len || value`, UTF-8, little-endian.
+fn encode_metadata(metadata: &[(String, String)]) -> Result<Vec<u8>> {
+    validate_metadata(metadata)?;
+    let mut out = Vec::new();
+    for (key, value) in metadata {
+        for text in [key, value] {
+            out.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            out.extend_from_slice(text.as_bytes());
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn validate_metadata(metadata: &[(String, String)]) -> Result<()> {
+    let mut len = 0_usize;
+    for (key, value) in metadata {
+        len = len.saturating_add(8 + key.len() + value.len());
+        if len > MAX_METADATA_LENGTH {
+            return Err(metadata_too_large());
+        }
+    }
+    Ok(())
+}
+
+fn parse_metadata(value: &[u8]) -> Result<Vec<(String, String)>> {
+    if value.len() > MAX_METADATA_LENGTH {
+        return Err(metadata_too_large());
+    }
+
+    let mut metadata = Vec::new();
+    let mut offset = 0;
+    while offset < value.len() {
+        let key = read_metadata_text(value, &mut offset)?;
+        let text = read_metadata_text(value, &mut offset)?;
+        metadata.push((key, text));
+    }
+    Ok(metadata)
+}
+
+fn read_metadata_text(value: &[u8], offset: &mut usize) -> Result<String> {
+    let len = read_u32_le(value, *offset).map_err(truncated)? as usize;
+    let start = *offset + 4;
+    let bytes = start
+        .checked_add(len)
+        .and_then(|end| value.get(start..end))
+        .ok_or_else(truncated_error)?;
+    *offset = start + len;
+    String::from_utf8(bytes.to_vec()).map_err(|_| {
+        TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            "Metadata keys and values must be UTF-8.",
+        )
+    })
+}
+
+fn metadata_too_large() -> TileError {
+    TileError::new(
+        TileErrorCode::InvalidFieldValue,
+        format!("Metadata exceeds {MAX_METADATA_LENGTH} bytes once encoded."),
+    )
+}
+
+fn push_record(body: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    body.push(tag);
+    body.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    body.extend_from_slice(value);
+}
+
+/// Parses the extension trailer at the start of `bytes` (the bytes following the stored
+/// payload). Returns the default extensions and a length of 0 when no trailer is present.
+/// A trailer checksum mismatch is `PayloadChecksumMismatch`, since the fixed header is
+/// intact; a tag recorded twice is `InvalidFieldValue`.
+pub(crate) fn parse_extensions(bytes: &[u8]) -> Result<(Extensions, usize)> {
+    let mut extensions = Extensions::default();
+    if bytes.get(..EXTENSION_MAGIC.len()) != Some(&EXTENSION_MAGIC[..]) {
+        return Ok((extensions, 0));
+    }
+
+    let body_len = read_u32_le(bytes, EXTENSION_MAGIC.len()).map_err(truncated)? as usize;
+    let body_start = EXTENSION_MAGIC.len() + 4;
+    let body_end = body_start
+        .checked_add(body_len)
+        .ok_or_else(truncated_error)?;
+    let body = bytes
+        .get(body_start..body_end)
+        .ok_or_else(truncated_error)?;
+    let expected_crc32 = read_u32_le(bytes, body_end).map_err(truncated)?;
+    let actual_crc32 = crc32(body);
+    if expected_crc32 != actual_crc32 {
+        return Err(TileError::new(
+            TileErrorCode::PayloadChecksumMismatch,
+            format!(
+                "Extension trailer checksum mismatch. expected={expected_crc32:08x} actual={actual_crc32:08x}"
+            ),
+        ));
+    }
+
+    let mut seen = [false; 256];
+    let mut offset = 0;
+    while offset < body.len() {
+        let tag = body[offset];
+        if core::mem::replace(&mut seen[usize::from(tag)], true) {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!("Duplicate extension tag 0x{tag:02x}."),
+            ));
+        }
+        let value_len = read_u32_le(body, offset + 1).map_err(truncated)? as usize;
+        let value_start = offset + 5;
+        let value = value_start
+            .checked_add(value_len)
+            .and_then(|value_end| body.get(value_start..value_end))
+            .ok_or_else(truncated_error)?;
+
+        match tag {
+            TAG_SPARSE => {
+                if !value.is_empty() {
+                    return Err(TileError::new(
+                        TileErrorCode::InvalidFieldValue,
+                        "Sparse extension record must be empty.",
+                    ));
+                }
+                extensions.sparse = true;
+            }
+            TAG_METADATA => extensions.metadata = parse_metadata(value)?,
+            TAG_GEOTRANSFORM => extensions.geotransform = Some(parse_geotransform(value)?),
+            TAG_BAND_CRC32 => extensions.band_crc32 = Some(parse_band_crc32(value)?),
+            TAG_FORMAT_MINOR => {
+                let &[minor] = value else {
+                    return Err(TileError::new(
+                        TileErrorCode::InvalidFieldValue,
+                        "Format minor extension record must be 1 byte.",
+                    ));
+                };
+                extensions.format_minor = minor;
+            }
+            TAG_PAYLOAD_ADLER32 => {
+                let Ok(value) = <[u8; 4]>::try_from(value) else {
+                    return Err(TileError::new(
+                        TileErrorCode::InvalidFieldValue,
+                        "Payload Adler-32 extension record must be 4 bytes.",
+                    ));
+                };
+                extensions.payload_adler32 = Some(u32::from_le_bytes(value));
+            }
+            TAG_PALETTE => extensions.palette = Some(parse_palette(value)?),
+            _ if tag & CRITICAL_TAG_BIT != 0 => {
+                return Err(TileError::new(
+                    TileErrorCode::InvalidFieldValue,
+                    format!("Unsupported critical extension tag 0x{tag:02x}."),
+                ));
+            }
+            _ => {}
+        }
+        offset = value_start + value_len;
+    }
+
+    Ok((extensions, body_end + 4))
+}
+
+/// Overwrites the value of the `tag` record in the trailer at the start of `trailer` and
+/// re-seals the trailer CRC. `value` must be as long as the stored value. Returns whether
+/// the record was found; the trailer is unchanged otherwise.
+pub(crate) fn rewrite_extension_record(trailer: &mut [u8], tag: u8, value: &[u8]) -> Result<bool> {
+    if trailer.get(..EXTENSION_MAGIC.len()) != Some(&EXTENSION_MAGIC[..]) {
+        return Ok(false);
+    }
+    let body_len = read_u32_le(trailer, EXTENSION_MAGIC.len()).map_err(truncated)? as usize;
+    let body_start = EXTENSION_MAGIC.len() + 4;
+    let body_end = body_start
+        .checked_add(body_len)
+        .filter(|&end| end + 4 <= trailer.len())
+        .ok_or_else(truncated_error)?;
+
+    let mut offset = body_start;
+    while offset < body_end {
+        let value_len = read_u32_le(trailer, offset + 1).map_err(truncated)? as usize;
+        let value_start = offset + 5;
+        if trailer[offset] == tag {
+            if value_len != value.len() {
+                return Err(TileError::new(
+                    TileErrorCode::InvalidFieldValue,
+                    format!(
+                        "Extension record 0x{tag:02x} is {value_len} bytes, expected {}.",
+                        value.len()
+                    ),
+                ));
+            }
+            trailer[value_start..value_start + value_len].copy_from_slice(value);
+            let body_crc32 = crc32(&trailer[body_start..body_end]);
+            trailer[body_end..body_end + 4].copy_from_slice(&body_crc32.to_le_bytes());
+            return Ok(true);
+        }
+        offset = value_start + value_len;
+    }
+    Ok(false)
+}
+
+fn truncated(_: TileError) -> TileError {
+    truncated_error()
+}
+
+fn truncated_error() -> TileError {
+    TileError::new(
+        TileErrorCode::InvalidPayloadLength,
+        "Extension trailer is truncated.",
+    )
+}
@@ -1,34 +1,103 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use std::fmt;
+extern crate alloc;
 
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+mod blob;
+mod checksum;
 mod common;
 mod consts;
+#[cfg(feature = "hashing")]
+mod content_hash;
 mod decoder;
 mod encoder;
+mod extension;
+#[cfg(feature = "image")]
+mod luma_image;
+#[cfg(feature = "bytemuck")]
+mod native_slice;
+mod raster;
+#[cfg(feature = "bytes")]
+mod shared_bytes;
+mod sparse;
+#[cfg(feature = "std")]
+mod tile_file;
 
-pub use consts::{TILE_FIXED_HEADER_LENGTH, TILE_VERSION_MAJOR};
-pub use decoder::{decode_payload_values, decode_tile_minimal, inspect_tile};
-pub use encoder::{encode_payload_values, encode_tile};
+#[cfg(feature = "std")]
+pub use blob::append_tile;
+#[cfg(feature = "rayon")]
+pub use blob::decode_blob_parallel;
+pub use blob::{build_blob_index, find_duplicate_tile_ids};
+pub use checksum::compute_header_crc32;
+#[cfg(feature = "std")]
+pub use common::xyz_tile_bounds;
+pub use common::{
+    expected_payload_length, max_tile_id, tile_id_sort_key, xyz_children, xyz_parent,
+};
+pub use consts::{
+    CUSTOM_MESH_KIND_MIN, HEADER_CHECKSUM_INPUT_LENGTH, HEADER_CHECKSUM_OFFSET, OFFSET_BANDS,
+    OFFSET_COLS, OFFSET_COMPRESSED_PAYLOAD_LENGTH, OFFSET_COMPRESSION, OFFSET_DTYPE_ENDIAN,
+    OFFSET_FORMAT_MAJOR, OFFSET_MESH_KIND, OFFSET_NO_DATA_KIND, OFFSET_NO_DATA_VALUE,
+    OFFSET_PAYLOAD_CHECKSUM, OFFSET_ROWS, OFFSET_TILE_ID, OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH,
+    TILE_FIXED_HEADER_LENGTH, TILE_VERSION_MAJOR, TILE_VERSION_MINOR,
+};
+pub use decoder::{
+    decode_payload_values, decode_tile_bands, decode_tile_into, decode_tile_minimal,
+    decode_tile_with_options, decode_tile_with_remainder, detect_layout, for_each_payload_value,
+    inspect_tile, inspect_tile_reserved, is_mesh_data_tile, parse_header_only,
+    read_integrity_fields, read_sample_uncompressed, tiles_structurally_equal, verify_identity,
+};
+pub use encoder::{
+    encode_from_shaped, encode_payload_from_iter, encode_payload_values,
+    encode_payload_values_with_policy, encode_tile, encode_tile_precompressed,
+    encode_tile_precompressed_with_options, encode_tile_prehashed, encode_tile_with_options,
+    refresh_payload_checksum, reinterpret_dtype, PayloadBuilder, PrehashedPayload,
+};
+#[cfg(feature = "std")]
+pub use encoder::{
+    encode_tile_to_writer, estimate_entropy, evaluate_compression, is_likely_compressible,
+};
+pub use raster::{mosaic_2x2_downsample, stack_bands};
+#[cfg(feature = "bytes")]
+pub use shared_bytes::decode_tile_bytes;
+#[cfg(feature = "std")]
+pub use tile_file::{read_tile_file, write_tile_file};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MeshKind {
     JisX0410,
     Xyz,
+    /// Experimental mesh system with a code in `200..=255`. Tile ids are not validated.
+    Custom(u8),
 }
 
 impl MeshKind {
-    pub(crate) fn code(self) -> u8 {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::JisX0410 => "jis-x0410",
+            Self::Xyz => "xyz",
+            Self::Custom(_) => "custom",
+        }
+    }
+
+    pub fn code(self) -> u8 {
         match self {
             Self::JisX0410 => 1,
             Self::Xyz => 2,
+            Self::Custom(code) => code,
         }
     }
 
-    pub(crate) fn from_code(code: u8) -> Result<Self> {
+    pub fn from_code(code: u8) -> Result<Self> {
         match code {
             1 => Ok(Self::JisX0410),
             2 => Ok(Self::Xyz),
+            CUSTOM_MESH_KIND_MIN..=u8::MAX => Ok(Self::Custom(code)),
             _ => Err(TileError::new(
                 TileErrorCode::InvalidFieldValue,
                 format!("Invalid mesh_kind code {code}."),
@@ -43,6 +112,15 @@ pub enum Endianness {
     Big,
 }
 
+impl Endianness {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Little => "LE",
+            Self::Big => "BE",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DType {
     Uint8,
@@ -53,9 +131,28 @@ pub enum DType {
     Int32,
     Float32,
     Float64,
+    /// 3-byte unsigned integer.
+    Uint24,
+    /// 3-byte two's-complement integer.
+    Int24,
 }
 
 impl DType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Uint8 => "uint8",
+            Self::Int8 => "int8",
+            Self::Uint16 => "uint16",
+            Self::Int16 => "int16",
+            Self::Uint32 => "uint32",
+            Self::Int32 => "int32",
+            Self::Float32 => "float32",
+            Self::Float64 => "float64",
+            Self::Uint24 => "uint24",
+            Self::Int24 => "int24",
+        }
+    }
+
     pub(crate) fn code(self) -> u8 {
         match self {
             Self::Uint8 => 0,
@@ -66,6 +163,8 @@ impl DType {
             Self::Int32 => 5,
             Self::Float32 => 6,
             Self::Float64 => 7,
+            Self::Uint24 => 8,
+            Self::Int24 => 9,
         }
     }
 
@@ -79,6 +178,8 @@ impl DType {
             5 => Ok(Self::Int32),
             6 => Ok(Self::Float32),
             7 => Ok(Self::Float64),
+            8 => Ok(Self::Uint24),
+            9 => Ok(Self::Int24),
             _ => Err(TileError::new(
                 TileErrorCode::InvalidFieldValue,
                 format!("Unsupported packed dtype code {code}."),
@@ -90,10 +191,28 @@ impl DType {
         match self {
             Self::Uint8 | Self::Int8 => 1,
             Self::Uint16 | Self::Int16 => 2,
+            Self::Uint24 | Self::Int24 => 3,
             Self::Uint32 | Self::Int32 | Self::Float32 => 4,
             Self::Float64 => 8,
         }
     }
+
+    /// Adjusts `value` so it can be encoded as this dtype, following `policy`.
+    pub fn cast_value(self, value: f64, policy: CastPolicy) -> Result<f64> {
+        common::cast_value(self, value, policy)
+    }
+}
+
+/// How [`DType::cast_value`] handles values that do not fit the dtype.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CastPolicy {
+    /// Reject non-integral or out-of-range values, as the encoder does.
+    #[default]
+    Reject,
+    /// Round to the nearest integer (ties to even), then reject out-of-range values.
+    Round,
+    /// Round, then saturate to the dtype's range. Float32 saturates to `±f32::MAX`.
+    Clamp,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -104,6 +223,13 @@ pub enum CompressionMode {
 }
 
 impl CompressionMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::DeflateRaw => "deflate-raw",
+        }
+    }
+
     pub(crate) fn code(self) -> u8 {
         match self {
             Self::None => 0,
@@ -141,24 +267,43 @@ impl TileDimensions {
         Ok(())
     }
 
-    pub(crate) fn total_samples(self) -> Result<u64> {
+    /// Number of samples, `rows * cols * bands`, checked for overflow.
+    ///
+    /// ```
+    /// use mesh_data_tile::TileDimensions;
+    ///
+    /// let dims = TileDimensions { rows: 2, cols: 3, bands: 4 };
+    /// assert_eq!(dims.total_samples()?, 24);
+    /// # Ok::<(), mesh_data_tile::TileError>(())
+    /// ```
+    pub fn total_samples(self) -> Result<u64> {
         let rows = u64::from(self.rows);
         let cols = u64::from(self.cols);
         let bands = u64::from(self.bands);
-        rows.checked_mul(cols)
-            .and_then(|v| v.checked_mul(bands))
-            .ok_or_else(|| {
-                TileError::new(
-                    TileErrorCode::InvalidFieldValue,
-                    "Invalid dimensions resulting in overflowed sample count.",
-                )
-            })
+        // rows * cols always fits; multiplying in bands can overflow.
+        (rows * cols).checked_mul(bands).ok_or_else(|| {
+            TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "rows * cols * bands = {}x{}x{} = {} samples overflows u64.",
+                    self.rows,
+                    self.cols,
+                    self.bands,
+                    u128::from(rows * cols) * u128::from(bands)
+                ),
+            )
+        })
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Headers compare field by field, except that a NaN `no_data` marker equals any other
+/// NaN marker, so tiles with `no_data_kind = 2` equal themselves.
+#[derive(Debug, Clone)]
 pub struct TileHeader {
     pub format_major: u8,
+    /// Minor version from the extension trailer; `0` when absent. Minor versions only add
+    /// optional data, so readers may branch on it but never need to reject it.
+    pub format_minor: u8,
     pub tile_id: u64,
     pub mesh_kind: MeshKind,
     pub dtype: DType,
@@ -172,6 +317,168 @@ pub struct TileHeader {
     pub payload_compressed_bytes: u64,
     pub payload_crc32: u32,
     pub header_crc32: u32,
+    /// The stored payload uses the sparse layout (see the spec's extension trailer). The
+    /// payload length fields and `payload_crc32` then describe the sparse bytes, not the
+    /// dense payload returned by the decoder.
+    pub sparse: bool,
+    /// Key-value tags from the extension trailer, in stored order. Not covered by the
+    /// header or payload checksums.
+    pub metadata: Vec<(String, String)>,
+    /// Affine geotransform from the extension trailer, in GDAL order; see
+    /// [`DecodedTile::pixel_to_world`].
+    pub geotransform: Option<[f64; 6]>,
+    /// CRC-32 of each band's samples in the dense uncompressed payload, from the extension
+    /// trailer. Lets [`decode_tile_bands`] verify only the requested bands.
+    pub band_crc32: Option<Vec<u32>>,
+    /// Adler-32 of the same bytes `payload_crc32` covers, from the extension trailer, for
+    /// interop with zlib-based systems. Verified alongside the CRC-32 when present.
+    pub payload_adler32: Option<u32>,
+    /// RGBA color of each class code (entry `i` for value `i`) from the extension trailer,
+    /// for single-band integer tiles. Covered by the trailer checksum, not the payload's.
+    pub palette: Option<Vec<[u8; 4]>>,
+}
+
+impl PartialEq for TileHeader {
+    fn eq(&self, other: &Self) -> bool {
+        // Destructured so a new field cannot be left out of the comparison.
+        let Self {
+            format_major,
+            format_minor,
+            tile_id,
+            mesh_kind,
+            dtype,
+            endianness,
+            compression,
+            dimensions,
+            no_data_kind,
+            no_data_value_raw,
+            no_data,
+            payload_uncompressed_bytes,
+            payload_compressed_bytes,
+            payload_crc32,
+            header_crc32,
+            sparse,
+            metadata,
+            geotransform,
+            band_crc32,
+            payload_adler32,
+            palette,
+        } = self;
+        let same_no_data = match (*no_data, other.no_data) {
+            (Some(a), Some(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        same_no_data
+            && *format_major == other.format_major
+            && *format_minor == other.format_minor
+            && *tile_id == other.tile_id
+            && *mesh_kind == other.mesh_kind
+            && *dtype == other.dtype
+            && *endianness == other.endianness
+            && *compression == other.compression
+            && *dimensions == other.dimensions
+            && *no_data_kind == other.no_data_kind
+            && *no_data_value_raw == other.no_data_value_raw
+            && *payload_uncompressed_bytes == other.payload_uncompressed_bytes
+            && *payload_compressed_bytes == other.payload_compressed_bytes
+            && *payload_crc32 == other.payload_crc32
+            && *header_crc32 == other.header_crc32
+            && *sparse == other.sparse
+            && *metadata == other.metadata
+            && *geotransform == other.geotransform
+            && *band_crc32 == other.band_crc32
+            && *payload_adler32 == other.payload_adler32
+            && *palette == other.palette
+    }
+}
+
+impl TileHeader {
+    /// One-line summary, e.g.
+    /// `MTI1 v1 tile=42 jis-x0410 uint16/LE deflate-raw 256x256x1 nodata=none 131072→6789 bytes ratio=0.052`.
+    pub fn describe(&self) -> String {
+        let no_data = match self.no_data {
+            Some(value) => format!("{value}"),
+            None => String::from("none"),
+        };
+        let mesh_kind = match self.mesh_kind {
+            MeshKind::Custom(code) => format!("custom-{code}"),
+            kind => String::from(kind.as_str()),
+        };
+        let ratio = if self.payload_uncompressed_bytes == 0 {
+            String::from("n/a")
+        } else {
+            format!(
+                "{:.3}",
+                self.payload_compressed_bytes as f64 / self.payload_uncompressed_bytes as f64
+            )
+        };
+        let version = match self.format_minor {
+            0 => format!("{}", self.format_major),
+            minor => format!("{}.{minor}", self.format_major),
+        };
+        format!(
+            "MTI1 v{} tile={} {} {}/{} {} {}x{}x{} nodata={} {}→{} bytes ratio={}{}",
+            version,
+            self.tile_id,
+            mesh_kind,
+            self.dtype.as_str(),
+            self.endianness.as_str(),
+            self.compression.as_str(),
+            self.dimensions.rows,
+            self.dimensions.cols,
+            self.dimensions.bands,
+            no_data,
+            self.payload_uncompressed_bytes,
+            self.payload_compressed_bytes,
+            ratio,
+            if self.sparse { " sparse" } else { "" },
+        )
+    }
+
+    /// `(rows, cols, bands)`: rows count top to bottom and are the tile's height, cols
+    /// count left to right and are its width.
+    pub fn shape(&self) -> (u32, u32, u8) {
+        (
+            self.dimensions.rows,
+            self.dimensions.cols,
+            self.dimensions.bands,
+        )
+    }
+
+    /// Whether samples are stored in the host's byte order, so they can be read without
+    /// swapping; see [`DecodedTile::into_native_endian`].
+    pub fn endianness_is_native(&self) -> bool {
+        self.endianness == common::native_endianness()
+    }
+
+    /// Share of the encoded tile taken by the 58-byte fixed header, from `0.0` to `1.0`.
+    /// The tile length is the stored payload length plus the trailer these fields encode
+    /// to. Useful for spotting tiles small enough that the header dominates.
+    pub fn header_overhead_fraction(&self) -> f64 {
+        let header_len = TILE_FIXED_HEADER_LENGTH as u64;
+        let total_len = header_len
+            .saturating_add(self.payload_compressed_bytes)
+            .saturating_add(extension::extension_trailer_len(self));
+        header_len as f64 / total_len as f64
+    }
+
+    /// Rough peak bytes allocated while decoding, from header fields alone: the
+    /// uncompressed payload, plus the compressed input held alongside it for compressed
+    /// tiles, plus the expanded dense payload for sparse tiles. Saturates at `u64::MAX`.
+    pub fn decode_memory_estimate(&self) -> u64 {
+        let mut total = self.payload_uncompressed_bytes;
+        if self.compression != CompressionMode::None {
+            total = total.saturating_add(self.payload_compressed_bytes);
+        }
+        if self.sparse {
+            let dense_len = u64::from(self.dimensions.rows)
+                .saturating_mul(u64::from(self.dimensions.cols))
+                .saturating_mul(u64::from(self.dimensions.bands))
+                .saturating_mul(self.dtype.byte_size() as u64);
+            total = total.saturating_add(dense_len);
+        }
+        total
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -182,8 +489,44 @@ pub struct TileEncodeInput<'a> {
     pub endianness: Endianness,
     pub compression: CompressionMode,
     pub dimensions: TileDimensions,
+    /// No-data marker. `Some(f64::NAN)` marks every NaN sample as no-data (float dtypes
+    /// only).
     pub no_data: Option<f64>,
     pub payload: &'a [u8],
+    /// Optional settings stored in the extension trailer. Use
+    /// `ExtensionOptions::default()` for a plain tile with no trailer.
+    pub extensions: ExtensionOptions<'a>,
+}
+
+/// Optional [`TileEncodeInput`] settings stored in the extension trailer (spec §12). New
+/// trailer features are added here, so build it with `..ExtensionOptions::default()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExtensionOptions<'a> {
+    /// Store only samples that differ from `no_data`, plus a presence bitmask. Requires
+    /// `no_data`. Falls back to the dense layout when that would not be smaller.
+    pub sparse: bool,
+    /// Key-value tags stored in the extension trailer. At most 64 KiB once encoded.
+    pub metadata: &'a [(String, String)],
+    /// Affine geotransform `[origin_x, pixel_width, row_rotation, origin_y,
+    /// column_rotation, pixel_height]` (GDAL order), stored in the extension trailer.
+    pub geotransform: Option<[f64; 6]>,
+    /// Store a CRC-32 of each band in the extension trailer; see
+    /// [`TileHeader::band_crc32`].
+    pub band_crc32: bool,
+    /// Also store an Adler-32 payload checksum in the extension trailer; see
+    /// [`TileHeader::payload_adler32`]. The header CRC-32 is always written.
+    pub payload_adler32: bool,
+    /// Up to 256 RGBA colors stored in the extension trailer; see [`TileHeader::palette`].
+    /// Only valid for single-band integer tiles.
+    pub palette: Option<&'a [[u8; 4]]>,
+}
+
+/// Result of compressing a payload with one mode; see [`evaluate_compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionReport {
+    pub mode: CompressionMode,
+    pub compressed_bytes: usize,
+    pub encode_nanos: u64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -196,6 +539,167 @@ pub struct EncodedTile {
 pub struct DecodedTile {
     pub header: TileHeader,
     pub payload: Vec<u8>,
+    /// The stored compressed payload, kept when decoding with
+    /// [`DecodeOptions::retain_compressed_payload`] so [`DecodedTile::reencode`] can write
+    /// it back without recompressing.
+    pub compressed_payload: Option<Vec<u8>>,
+    /// `false` only for tiles decoded with
+    /// [`DecodeOptions::return_payload_on_crc_mismatch`] whose payload failed its CRC-32 or
+    /// Adler-32 check. Such a payload may be arbitrarily damaged.
+    pub payload_crc_valid: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Re-derive `rows * cols * bands * sizeof(dtype)` after decompression and compare it
+    /// with the payload length. The stored uncompressed length is always checked, so this
+    /// can be turned off for trusted tiles.
+    pub verify_dimensions_match_payload: bool,
+    /// Check the header CRC-32. Only turn this off for bytes verified by other means.
+    pub verify_header_crc32: bool,
+    /// Check the payload CRC-32, and the payload Adler-32 when the tile stores one. Only
+    /// turn this off for bytes verified by other means.
+    pub verify_payload_crc32: bool,
+    /// Reject tiles whose stored uncompressed length or dense payload length exceeds this
+    /// many bytes, before anything is decompressed.
+    pub max_uncompressed_bytes: Option<u64>,
+    /// Reject tiles whose declared compressed length exceeds this many bytes, right after
+    /// the fixed header is parsed and before the payload is located or read.
+    pub max_compressed_bytes: Option<u64>,
+    /// Ignore bytes after the tile and its extension trailer. When `false` they are an
+    /// `InvalidPayloadLength` error.
+    pub allow_trailing_bytes: bool,
+    /// Accept a dense tile whose stored uncompressed length is wrong when the payload
+    /// matches both the payload CRC-32 and the length implied by the dimensions and dtype.
+    /// The returned header keeps the stored value; see
+    /// [`DecodedTile::has_wrong_length_field`].
+    pub trust_payload_crc_over_length: bool,
+    /// Keep a copy of the stored bytes of compressed tiles in
+    /// [`DecodedTile::compressed_payload`]. The payload CRC-32 is then always checked, even
+    /// with `verify_payload_crc32` off.
+    pub retain_compressed_payload: bool,
+    /// Return the decoded tile, with [`DecodedTile::payload_crc_valid`] set to `false`,
+    /// when the payload fails its checksum instead of a `PayloadChecksumMismatch` error.
+    /// For forensic recovery only: the samples may be wrong anywhere, including values
+    /// that look plausible, and must not be trusted or re-encoded as good data. Structural
+    /// errors (decompression, lengths, sparse expansion) are still returned as errors.
+    pub return_payload_on_crc_mismatch: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            verify_dimensions_match_payload: true,
+            verify_header_crc32: true,
+            verify_payload_crc32: true,
+            max_uncompressed_bytes: None,
+            max_compressed_bytes: None,
+            allow_trailing_bytes: true,
+            trust_payload_crc_over_length: false,
+            retain_compressed_payload: false,
+            return_payload_on_crc_mismatch: false,
+        }
+    }
+}
+
+/// Encoder settings for [`encode_tile_with_options`]; [`encode_tile`] uses the defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// Deflate level `0..=9` for `deflate-raw`. Defaults to `9`.
+    pub compression_level: u32,
+    /// Store a `deflate-raw` request uncompressed when compressing would not make the
+    /// payload smaller. High-entropy payloads (see [`is_likely_compressible`]) skip the
+    /// compression attempt.
+    pub compression_auto: bool,
+    /// Minor version recorded in the extension trailer. Defaults to
+    /// [`TILE_VERSION_MINOR`]; `0` writes no record.
+    pub format_minor: u8,
+    /// Match-finding strategy for `deflate-raw`. This only changes which compressed bytes
+    /// are produced; every strategy emits a standard raw deflate stream, so the format and
+    /// decoding are unaffected.
+    pub deflate_strategy: DeflateStrategy,
+    /// Store the payload uncompressed regardless of `input.compression`, so the encoded
+    /// bytes depend only on the input. The header, extension trailer, and an uncompressed
+    /// payload are always byte-for-byte reproducible; `deflate-raw` output is not
+    /// guaranteed to be stable across `flate2` / `miniz_oxide` versions.
+    pub deterministic: bool,
+}
+
+/// Deflate strategy, as zlib's `Z_DEFAULT_STRATEGY`, `Z_FILTERED`, `Z_HUFFMAN_ONLY`, and
+/// `Z_RLE`. `Filtered` can compress better on dtype-structured data such as float
+/// samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeflateStrategy {
+    #[default]
+    Default,
+    /// Only use matches of at least 5 bytes, favoring Huffman coding of literals.
+    Filtered,
+    /// Skip match finding and only Huffman-code literals.
+    HuffmanOnly,
+    /// Only match the previous byte (run-length encoding).
+    Rle,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: 9,
+            compression_auto: false,
+            format_minor: TILE_VERSION_MINOR,
+            deflate_strategy: DeflateStrategy::Default,
+            deterministic: false,
+        }
+    }
+}
+
+/// Where [`detect_layout`] found the fixed header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutHint {
+    /// The buffer starts with the magic, as the format requires.
+    HeaderFirst,
+    /// The magic starts the last 58 bytes instead: the payload was likely written before
+    /// the header.
+    HeaderLast,
+    /// The magic is at neither position.
+    Unrecognized,
+}
+
+/// Header fields for an external integrity manifest; see [`read_integrity_fields`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegrityFields {
+    pub tile_id: u64,
+    pub payload_crc32: u32,
+    pub uncompressed_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+/// A validated XYZ tile_id (`zoom << 58 | quadkey_integer`, spec §4.4). Converts to and
+/// from the raw `u64` stored in the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct XyzTileId(u64);
+
+/// A geographic extent in EPSG:4326 degrees; see [`xyz_tile_bounds`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileBounds {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+}
+
+/// A decoded tile whose payload may share the input buffer; see `decode_tile_bytes`.
+#[cfg(feature = "bytes")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedTileBytes {
+    pub header: TileHeader,
+    pub payload: bytes::Bytes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileIndexEntry {
+    pub tile_id: u64,
+    pub offset: usize,
+    pub total_len: usize,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -211,6 +715,7 @@ pub enum TileErrorCode {
     CompressionFailed,
     DecompressionFailed,
     PayloadChecksumMismatch,
+    IoError,
 }
 
 impl TileErrorCode {
@@ -227,6 +732,60 @@ impl TileErrorCode {
             Self::CompressionFailed => "COMPRESSION_FAILED",
             Self::DecompressionFailed => "DECOMPRESSION_FAILED",
             Self::PayloadChecksumMismatch => "PAYLOAD_CHECKSUM_MISMATCH",
+            Self::IoError => "IO_ERROR",
+        }
+    }
+
+    /// Stable numeric code for telemetry and FFI. The mapping is append-only: a code
+    /// never changes meaning, and new variants take the next unused number.
+    ///
+    /// | Code | Variant |
+    /// | ---: | --- |
+    /// | 1 | `InvalidMagic` |
+    /// | 2 | `UnsupportedVersion` |
+    /// | 3 | `InvalidHeaderLength` |
+    /// | 4 | `InvalidFieldValue` |
+    /// | 5 | `MissingRequiredField` |
+    /// | 6 | `HeaderChecksumMismatch` |
+    /// | 7 | `InvalidPayloadLength` |
+    /// | 8 | `UnsupportedCompression` |
+    /// | 9 | `CompressionFailed` |
+    /// | 10 | `DecompressionFailed` |
+    /// | 11 | `PayloadChecksumMismatch` |
+    /// | 12 | `IoError` |
+    pub fn numeric(self) -> u16 {
+        match self {
+            Self::InvalidMagic => 1,
+            Self::UnsupportedVersion => 2,
+            Self::InvalidHeaderLength => 3,
+            Self::InvalidFieldValue => 4,
+            Self::MissingRequiredField => 5,
+            Self::HeaderChecksumMismatch => 6,
+            Self::InvalidPayloadLength => 7,
+            Self::UnsupportedCompression => 8,
+            Self::CompressionFailed => 9,
+            Self::DecompressionFailed => 10,
+            Self::PayloadChecksumMismatch => 11,
+            Self::IoError => 12,
+        }
+    }
+
+    /// Inverse of [`TileErrorCode::numeric`]; `None` for unassigned codes.
+    pub fn from_numeric(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(Self::InvalidMagic),
+            2 => Some(Self::UnsupportedVersion),
+            3 => Some(Self::InvalidHeaderLength),
+            4 => Some(Self::InvalidFieldValue),
+            5 => Some(Self::MissingRequiredField),
+            6 => Some(Self::HeaderChecksumMismatch),
+            7 => Some(Self::InvalidPayloadLength),
+            8 => Some(Self::UnsupportedCompression),
+            9 => Some(Self::CompressionFailed),
+            10 => Some(Self::DecompressionFailed),
+            11 => Some(Self::PayloadChecksumMismatch),
+            12 => Some(Self::IoError),
+            _ => None,
         }
     }
 }
@@ -258,9 +817,9 @@ impl fmt::Display for TileError {
     }
 }
 
-impl std::error::Error for TileError {}
+impl core::error::Error for TileError {}
 
-pub type Result<T> = std::result::Result<T, TileError>;
+pub type Result<T> = core::result::Result<T, TileError>;
 
 #[cfg(test)]
 mod tests {
@@ -274,6 +833,39 @@ mod tests {
         }
     }
 
+    fn decoded_tile(
+        dtype: DType,
+        dimensions: TileDimensions,
+        values: &[f64],
+        no_data: Option<f64>,
+    ) -> DecodedTile {
+        let payload = encode_payload_values(dtype, Endianness::Little, values)
+            .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions,
+            no_data,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        decode_tile_minimal(&encoded.bytes).expect("decode tile")
+    }
+
+    fn tile_values(tile: &DecodedTile) -> Vec<Option<f64>> {
+        decode_payload_values(
+            tile.header.dtype,
+            tile.header.endianness,
+            &tile.payload,
+            tile.header.no_data,
+        )
+        .expect("decode payload values")
+    }
+
     #[test]
     fn roundtrip_uncompressed_payload() {
         let payload =
@@ -289,6 +881,7 @@ mod tests {
             dimensions: tile_dims(),
             no_data: None,
             payload: &payload,
+            extensions: ExtensionOptions::default(),
         })
         .expect("encode tile");
 
@@ -322,6 +915,7 @@ mod tests {
             dimensions: tile_dims(),
             no_data: None,
             payload: &payload,
+            extensions: ExtensionOptions::default(),
         })
         .expect("encode tile");
 
@@ -345,6 +939,7 @@ mod tests {
             dimensions: tile_dims(),
             no_data: None,
             payload: &payload,
+            extensions: ExtensionOptions::default(),
         })
         .expect("encode tile");
 
@@ -370,6 +965,7 @@ mod tests {
             dimensions: tile_dims(),
             no_data: None,
             payload: &payload,
+            extensions: ExtensionOptions::default(),
         })
         .expect_err("should reject bad xyz tile id");
 
@@ -391,6 +987,7 @@ mod tests {
             dimensions: tile_dims(),
             no_data: Some(20.0),
             payload: &payload,
+            extensions: ExtensionOptions::default(),
         })
         .expect("encode tile");
 
@@ -405,4 +1002,3853 @@ mod tests {
 
         assert_eq!(values, vec![Some(10.0), None, Some(30.0), None]);
     }
+
+    #[test]
+    fn appends_tiles_to_writer() {
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+        let input = TileEncodeInput {
+            tile_id: 7,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+
+        let mut blob = Vec::new();
+        let first_len = append_tile(&mut blob, input.clone()).expect("append first tile");
+        let second_len = append_tile(
+            &mut blob,
+            TileEncodeInput {
+                tile_id: 8,
+                ..input
+            },
+        )
+        .expect("append second tile");
+        assert_eq!(blob.len(), first_len + second_len);
+
+        let first = decode_tile_minimal(&blob[..first_len]).expect("decode first tile");
+        let second = decode_tile_minimal(&blob[first_len..]).expect("decode second tile");
+        assert_eq!(first.header.tile_id, 7);
+        assert_eq!(second.header.tile_id, 8);
+        assert_eq!(second.payload, payload);
+    }
+
+    #[test]
+    fn builds_index_over_three_tile_blob() {
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+
+        let mut blob = Vec::new();
+        let mut lengths = Vec::new();
+        for (tile_id, compression) in [
+            (100, CompressionMode::None),
+            (200, CompressionMode::DeflateRaw),
+            (300, CompressionMode::None),
+        ] {
+            let written = append_tile(
+                &mut blob,
+                TileEncodeInput {
+                    tile_id,
+                    mesh_kind: MeshKind::JisX0410,
+                    dtype: DType::Uint8,
+                    endianness: Endianness::Little,
+                    compression,
+                    dimensions: tile_dims(),
+                    no_data: None,
+                    payload: &payload,
+                    extensions: ExtensionOptions::default(),
+                },
+            )
+            .expect("append tile");
+            lengths.push(written);
+        }
+
+        let index = build_blob_index(&blob).expect("build index");
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.iter().map(|entry| entry.tile_id).collect::<Vec<_>>(),
+            vec![100, 200, 300]
+        );
+        assert_eq!(index[0].offset, 0);
+        assert_eq!(index[1].offset, lengths[0]);
+        assert_eq!(index[2].offset, lengths[0] + lengths[1]);
+        for (entry, len) in index.iter().zip(&lengths) {
+            assert_eq!(entry.total_len, *len);
+            let tile = &blob[entry.offset..entry.offset + entry.total_len];
+            let decoded = decode_tile_minimal(tile).expect("decode indexed tile");
+            assert_eq!(decoded.header.tile_id, entry.tile_id);
+        }
+
+        let truncated = &blob[..blob.len() - 1];
+        let error = build_blob_index(truncated).expect_err("truncated blob should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn decodes_blob_in_parallel_preserving_order() {
+        let mut blob = Vec::new();
+        for tile_id in 0..16_u64 {
+            let payload = encode_payload_values(
+                DType::Uint8,
+                Endianness::Little,
+                &[tile_id as f64, 1.0, 2.0, 3.0],
+            )
+            .expect("encode payload values");
+            append_tile(
+                &mut blob,
+                TileEncodeInput {
+                    tile_id,
+                    mesh_kind: MeshKind::JisX0410,
+                    dtype: DType::Uint8,
+                    endianness: Endianness::Little,
+                    compression: CompressionMode::DeflateRaw,
+                    dimensions: tile_dims(),
+                    no_data: None,
+                    payload: &payload,
+                    extensions: ExtensionOptions::default(),
+                },
+            )
+            .expect("append tile");
+        }
+
+        let tiles = decode_blob_parallel(&blob).expect("decode blob");
+        assert_eq!(tiles.len(), 16);
+        for (index, tile) in tiles.iter().enumerate() {
+            assert_eq!(tile.header.tile_id, index as u64);
+            assert_eq!(tile.payload[0], index as u8);
+        }
+
+        let mut corrupt = blob.clone();
+        corrupt[0] = b'X';
+        assert!(decode_blob_parallel(&corrupt).is_err());
+        let truncated = &blob[..blob.len() - 1];
+        let error = decode_blob_parallel(truncated).expect_err("truncated blob should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+    }
+
+    #[test]
+    fn encodes_prehashed_payload_from_chunks() {
+        let mut builder = PayloadBuilder::new();
+        for chunk in [[1_u8, 2], [3, 4]] {
+            builder.push_chunk(&chunk);
+        }
+        assert_eq!(builder.len(), 4);
+        let prehashed = builder.finish();
+
+        let input = TileEncodeInput {
+            tile_id: 9,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &prehashed.bytes,
+            extensions: ExtensionOptions::default(),
+        };
+        let expected = encode_tile(input.clone()).expect("encode tile");
+        let encoded =
+            encode_tile_prehashed(input.clone(), prehashed.crc32).expect("encode prehashed tile");
+        assert_eq!(encoded, expected);
+
+        let wrong = encode_tile_prehashed(input, prehashed.crc32 ^ 1).expect("encode wrong crc");
+        let error = decode_tile_minimal(&wrong.bytes).expect_err("should fail");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+    }
+
+    #[test]
+    fn sniffs_mesh_data_tiles() {
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+
+        assert!(is_mesh_data_tile(&encoded.bytes));
+        assert!(is_mesh_data_tile(
+            &encoded.bytes[..TILE_FIXED_HEADER_LENGTH]
+        ));
+        assert!(!is_mesh_data_tile(
+            &encoded.bytes[..TILE_FIXED_HEADER_LENGTH - 1]
+        ));
+        assert!(!is_mesh_data_tile(&[]));
+
+        let mut wrong_version = encoded.bytes.clone();
+        wrong_version[4] = 2;
+        assert!(!is_mesh_data_tile(&wrong_version));
+
+        let mut wrong_magic = encoded.bytes;
+        wrong_magic[0] = b'X';
+        assert!(!is_mesh_data_tile(&wrong_magic));
+    }
+
+    #[test]
+    fn resamples_window_with_nearest_neighbor_and_no_data() {
+        let tile = decoded_tile(DType::Uint16, tile_dims(), &[1.0, 2.0, 3.0, 0.0], Some(0.0));
+
+        let upsampled = tile
+            .resample_window((0.0, 0.0, 2.0, 2.0), 4, 4)
+            .expect("resample full tile");
+        assert_eq!(
+            tile_values(&upsampled),
+            [
+                [Some(1.0), Some(1.0), Some(2.0), Some(2.0)],
+                [Some(1.0), Some(1.0), Some(2.0), Some(2.0)],
+                [Some(3.0), Some(3.0), None, None],
+                [Some(3.0), Some(3.0), None, None],
+            ]
+            .concat()
+        );
+
+        let shifted = tile
+            .resample_window((-1.0, 0.5, 1.0, 2.5), 2, 2)
+            .expect("resample shifted window");
+        assert_eq!(tile_values(&shifted), vec![None, None, Some(2.0), None]);
+
+        let reencoded = encode_tile(TileEncodeInput {
+            tile_id: shifted.header.tile_id,
+            mesh_kind: shifted.header.mesh_kind,
+            dtype: shifted.header.dtype,
+            endianness: shifted.header.endianness,
+            compression: shifted.header.compression,
+            dimensions: shifted.header.dimensions,
+            no_data: shifted.header.no_data,
+            payload: &shifted.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("re-encode resampled tile");
+        assert_eq!(reencoded.header, shifted.header);
+
+        let without_no_data = decoded_tile(DType::Uint16, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let error = without_no_data
+            .resample_window((-1.0, -1.0, 1.0, 1.0), 2, 2)
+            .expect_err("out-of-bounds window without no_data should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn header_offsets_are_non_overlapping_and_fit_header() {
+        let fields = [
+            (OFFSET_FORMAT_MAJOR, 1),
+            (OFFSET_TILE_ID, 8),
+            (OFFSET_MESH_KIND, 1),
+            (OFFSET_DTYPE_ENDIAN, 1),
+            (OFFSET_COMPRESSION, 1),
+            (OFFSET_ROWS, 4),
+            (OFFSET_COLS, 4),
+            (OFFSET_BANDS, 1),
+            (OFFSET_NO_DATA_KIND, 1),
+            (OFFSET_NO_DATA_VALUE, 8),
+            (OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH, 8),
+            (OFFSET_COMPRESSED_PAYLOAD_LENGTH, 8),
+            (OFFSET_PAYLOAD_CHECKSUM, 4),
+            (HEADER_CHECKSUM_OFFSET, 4),
+        ];
+
+        let mut previous_end = 4;
+        for (offset, size) in fields {
+            assert!(
+                offset >= previous_end,
+                "field at {offset} overlaps previous field"
+            );
+            previous_end = offset + size;
+        }
+        assert!(previous_end <= TILE_FIXED_HEADER_LENGTH);
+        assert_eq!(HEADER_CHECKSUM_INPUT_LENGTH, HEADER_CHECKSUM_OFFSET);
+        assert_eq!(
+            consts::HEADER_FIELDS
+                .iter()
+                .map(|(_, size)| size)
+                .sum::<usize>(),
+            TILE_FIXED_HEADER_LENGTH
+        );
+    }
+
+    #[test]
+    fn skips_dimension_check_when_disabled() {
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 5,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+
+        // Claim 2 bands in the header while keeping the 4-byte payload and its lengths.
+        let mut forged = encoded.bytes;
+        forged[OFFSET_BANDS] = 2;
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+
+        let error = decode_tile_minimal(&forged).expect_err("dimension check should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+
+        let decoded = decode_tile_with_options(
+            &forged,
+            &DecodeOptions {
+                verify_dimensions_match_payload: false,
+                ..DecodeOptions::default()
+            },
+        )
+        .expect("decode without dimension check");
+        assert_eq!(decoded.payload, payload);
+    }
+
+    #[test]
+    fn roundtrips_sparse_tiles() {
+        let dimensions = TileDimensions {
+            rows: 4,
+            cols: 4,
+            bands: 1,
+        };
+        let mut values = vec![0.0; 16];
+        values[5] = 7.0;
+        values[15] = 9.0;
+        let payload = encode_payload_values(DType::Uint16, Endianness::Little, &values)
+            .expect("encode payload values");
+        let input = TileEncodeInput {
+            tile_id: 11,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions,
+            no_data: Some(0.0),
+            payload: &payload,
+            extensions: ExtensionOptions {
+                sparse: true,
+                ..ExtensionOptions::default()
+            },
+        };
+
+        for compression in [CompressionMode::None, CompressionMode::DeflateRaw] {
+            let encoded = encode_tile(TileEncodeInput {
+                compression,
+                ..input.clone()
+            })
+            .expect("encode sparse tile");
+            assert!(encoded.header.sparse);
+            // 2-byte bitmask + two stored uint16 samples.
+            assert_eq!(encoded.header.payload_uncompressed_bytes, 6);
+
+            let decoded = decode_tile_minimal(&encoded.bytes).expect("decode sparse tile");
+            assert!(decoded.header.sparse);
+            assert_eq!(decoded.header, encoded.header);
+            assert_eq!(decoded.payload, payload);
+
+            let index = build_blob_index(&encoded.bytes).expect("index sparse tile");
+            assert_eq!(index[0].total_len, encoded.bytes.len());
+        }
+
+        let dense_values: Vec<f64> = (1..=16).map(f64::from).collect();
+        let dense_payload = encode_payload_values(DType::Uint16, Endianness::Little, &dense_values)
+            .expect("encode payload values");
+        let dense = encode_tile(TileEncodeInput {
+            payload: &dense_payload,
+            ..input.clone()
+        })
+        .expect("encode tile without no_data samples");
+        assert!(!dense.header.sparse);
+        assert_eq!(
+            dense.bytes.len(),
+            TILE_FIXED_HEADER_LENGTH + dense_payload.len()
+        );
+
+        let error = encode_tile(TileEncodeInput {
+            no_data: None,
+            ..input
+        })
+        .expect_err("sparse without no_data should fail");
+        assert_eq!(error.code, TileErrorCode::MissingRequiredField);
+    }
+
+    #[test]
+    fn describes_header_on_one_line() {
+        let payload = encode_payload_values(DType::Int16, Endianness::Big, &[-9999.0; 4])
+            .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 533945,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Int16,
+            endianness: Endianness::Big,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: Some(-9999.0),
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        assert_eq!(
+            encoded.header.describe(),
+            "MTI1 v1 tile=533945 jis-x0410 int16/BE none 2x2x1 nodata=-9999 8→8 bytes ratio=1.000"
+        );
+    }
+
+    #[test]
+    fn reports_payload_details_on_checksum_mismatch() {
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+        let input = TileEncodeInput {
+            tile_id: 3,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+
+        let mut corrupted = encode_tile(input.clone()).expect("encode tile").bytes;
+        corrupted[TILE_FIXED_HEADER_LENGTH + 3] = 0xff;
+        let error = decode_tile_minimal(&corrupted).expect_err("should fail");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+        assert!(error
+            .message
+            .contains("payload_len=4 first_bytes=[01 02 03 ff]"));
+        assert!(error.message.contains("payload stored uncompressed"));
+
+        let compressed = encode_tile_prehashed(
+            TileEncodeInput {
+                compression: CompressionMode::DeflateRaw,
+                ..input
+            },
+            0,
+        )
+        .expect("encode tile with wrong crc");
+        let error = decode_tile_minimal(&compressed.bytes).expect_err("should fail");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+        assert!(error.message.contains("first_bytes=[01 02 03 04]"));
+        assert!(error.message.contains("decompressed cleanly"));
+    }
+
+    #[test]
+    fn little_endian_integer_fast_path_matches_per_value_encoding() {
+        let cases = [
+            (DType::Uint8, vec![0.0, 1.0, 255.0]),
+            (DType::Int8, vec![-128.0, -1.0, 127.0]),
+            (DType::Uint16, vec![0.0, 258.0, 65535.0]),
+            (DType::Int16, vec![-32768.0, -2.0, 32767.0]),
+            (DType::Uint32, vec![0.0, 16909060.0, 4294967295.0]),
+            (DType::Int32, vec![-2147483648.0, -3.0, 2147483647.0]),
+        ];
+
+        for (dtype, values) in cases {
+            let encoded = encode_payload_values(dtype, Endianness::Little, &values)
+                .expect("encode payload values");
+            let mut expected = vec![0_u8; values.len() * dtype.byte_size()];
+            for (value, out) in values
+                .iter()
+                .zip(expected.chunks_exact_mut(dtype.byte_size()))
+            {
+                common::write_numeric_value(dtype, Endianness::Little, *value, true, out)
+                    .expect("write numeric value");
+            }
+            assert_eq!(encoded, expected, "{dtype:?}");
+
+            let error = encode_payload_values(dtype, Endianness::Little, &[0.5])
+                .expect_err("non-integer should fail");
+            assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        }
+
+        let error = encode_payload_values(DType::Uint16, Endianness::Little, &[65536.0])
+            .expect_err("out-of-range should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn verifies_tile_identity_from_header() {
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 5339,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+
+        verify_identity(&encoded.bytes, 5339, tile_dims()).expect("identity should match");
+
+        let error = verify_identity(&encoded.bytes, 5340, tile_dims())
+            .expect_err("tile_id mismatch should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        assert!(error.message.contains("tile_id mismatch"));
+
+        let error = verify_identity(
+            &encoded.bytes,
+            5339,
+            TileDimensions {
+                rows: 1,
+                cols: 4,
+                bands: 1,
+            },
+        )
+        .expect_err("dimension mismatch should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        assert!(error.message.contains("expected=1x4x1 got=2x2x1"));
+    }
+
+    #[test]
+    fn encodes_payload_from_iterator() {
+        let values = [1.5, -2.0, 3.25, 4.0];
+        let encoded = encode_payload_from_iter(
+            DType::Float32,
+            Endianness::Big,
+            values.len(),
+            values.iter().copied(),
+        )
+        .expect("encode payload from iterator");
+        let expected = encode_payload_values(DType::Float32, Endianness::Big, &values)
+            .expect("encode payload values");
+        assert_eq!(encoded, expected);
+
+        let error = encode_payload_from_iter(DType::Uint8, Endianness::Little, 3, [1.0, 2.0])
+            .expect_err("too few values should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+
+        let error = encode_payload_from_iter(DType::Uint8, Endianness::Little, 1, [1.0, 2.0])
+            .expect_err("too many values should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn exports_single_band_tiles_as_luma_images() {
+        let dims = TileDimensions {
+            rows: 2,
+            cols: 3,
+            bands: 1,
+        };
+        let tile = decoded_tile(
+            DType::Uint8,
+            dims,
+            &[1.0, 2.0, 3.0, 255.0, 5.0, 6.0],
+            Some(255.0),
+        );
+        let image = tile.to_luma_image().expect("export uint8 tile");
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(0, 1).0, [0]);
+        assert_eq!(image.get_pixel(2, 0).0, [3]);
+
+        let tile = decoded_tile(
+            DType::Uint16,
+            dims,
+            &[1.0, 2.0, 3.0, 4.0, 5.0, 60000.0],
+            None,
+        );
+        let image = tile.to_luma16().expect("export uint16 tile");
+        assert_eq!(image.get_pixel(2, 1).0, [60000]);
+        let error = tile.to_luma_image().expect_err("uint16 is not uint8");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+
+        let tile = decoded_tile(
+            DType::Uint8,
+            TileDimensions {
+                rows: 1,
+                cols: 1,
+                bands: 2,
+            },
+            &[1.0, 2.0],
+            None,
+        );
+        let error = tile.to_luma_image().expect_err("multi-band should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+
+        let tile = decoded_tile(DType::Float32, dims, &[0.5; 6], None);
+        let error = tile.to_luma16().expect_err("float should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn roundtrips_smallest_tiles_for_every_dtype() {
+        let single = TileDimensions {
+            rows: 1,
+            cols: 1,
+            bands: 1,
+        };
+        let dtypes = [
+            (DType::Uint8, 200.0),
+            (DType::Int8, -100.0),
+            (DType::Uint16, 60000.0),
+            (DType::Int16, -30000.0),
+            (DType::Uint32, 4000000000.0),
+            (DType::Int32, -2000000000.0),
+            (DType::Float32, 1.5),
+            (DType::Float64, -0.125),
+        ];
+
+        for (dtype, value) in dtypes {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                for compression in [CompressionMode::None, CompressionMode::DeflateRaw] {
+                    let payload = encode_payload_values(dtype, endianness, &[value])
+                        .expect("encode payload values");
+                    assert_eq!(payload.len(), dtype.byte_size());
+
+                    let encoded = encode_tile(TileEncodeInput {
+                        tile_id: 0,
+                        mesh_kind: MeshKind::JisX0410,
+                        dtype,
+                        endianness,
+                        compression,
+                        dimensions: single,
+                        no_data: None,
+                        payload: &payload,
+                        extensions: ExtensionOptions::default(),
+                    })
+                    .expect("encode 1x1x1 tile");
+                    assert!(encoded.header.payload_compressed_bytes > 0);
+
+                    let decoded = decode_tile_minimal(&encoded.bytes).expect("decode 1x1x1 tile");
+                    assert_eq!(decoded.header, encoded.header);
+                    assert_eq!(decoded.payload, payload);
+                    assert_eq!(tile_values(&decoded), vec![Some(value)], "{dtype:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn converts_big_endian_tiles_to_native_endianness() {
+        let values = [1.0, 258.0, 65535.0, 0.0];
+        let payload = encode_payload_values(DType::Uint16, Endianness::Big, &values)
+            .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Big,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: Some(0.0),
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode tile");
+
+        assert_eq!(
+            decoded.header.endianness_is_native(),
+            cfg!(target_endian = "big")
+        );
+        let native = decoded.clone().into_native_endian().expect("to native");
+        assert_eq!(native.header.endianness, common::native_endianness());
+        assert!(native.header.endianness_is_native());
+        assert_eq!(native.header.no_data, Some(0.0));
+        assert_eq!(tile_values(&native), tile_values(&decoded));
+        assert_eq!(
+            native.clone().into_native_endian().expect("already native"),
+            native
+        );
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn views_native_payload_as_typed_slices() {
+        let tile = decoded_tile(DType::Uint16, tile_dims(), &[1.0, 2.0, 3.0, 65535.0], None);
+        if common::native_endianness() == Endianness::Little {
+            assert_eq!(tile.as_slice_u16().expect("view as u16"), &[1, 2, 3, 65535]);
+        }
+        let error = tile.as_slice_i16().expect_err("dtype mismatch should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+
+        let payload = encode_payload_values(DType::Int32, Endianness::Big, &[-1.0, 2.0, 3.0, 4.0])
+            .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Int32,
+            endianness: Endianness::Big,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let big = decode_tile_minimal(&encoded.bytes).expect("decode tile");
+        if common::native_endianness() == Endianness::Little {
+            let error = big.as_slice_i32().expect_err("big-endian view should fail");
+            assert!(error.message.contains("into_native_endian"));
+        }
+        let native = big.into_native_endian().expect("to native");
+        assert_eq!(native.as_slice_i32().expect("view as i32"), &[-1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn header_only_buffers_fail_without_panicking() {
+        let error = decode_tile_minimal(&[0_u8; TILE_FIXED_HEADER_LENGTH])
+            .expect_err("all-zero header should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidMagic);
+
+        let tile = decoded_tile(DType::Float64, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        for compression in [CompressionMode::None, CompressionMode::DeflateRaw] {
+            let encoded = encode_tile(TileEncodeInput {
+                tile_id: 7,
+                mesh_kind: MeshKind::JisX0410,
+                dtype: DType::Float64,
+                endianness: Endianness::Little,
+                compression,
+                dimensions: tile_dims(),
+                no_data: None,
+                payload: &tile.payload,
+                extensions: ExtensionOptions::default(),
+            })
+            .expect("encode tile");
+
+            for declared_len in [0, 1, 32, u64::MAX - 57, u64::MAX] {
+                // Keep exactly the fixed header, then re-seal it with forged lengths so the
+                // parser gets past the header checksum.
+                let mut header = encoded.bytes[..TILE_FIXED_HEADER_LENGTH].to_vec();
+                for offset in [
+                    OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH,
+                    OFFSET_COMPRESSED_PAYLOAD_LENGTH,
+                ] {
+                    header[offset..offset + 8].copy_from_slice(&declared_len.to_le_bytes());
+                }
+                let header_crc32 = crc32fast::hash(&header[..HEADER_CHECKSUM_INPUT_LENGTH]);
+                header[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+                    .copy_from_slice(&header_crc32.to_le_bytes());
+
+                // Header-only input is inspectable as long as the declared length fits.
+                assert_eq!(inspect_tile(&header).is_ok(), declared_len <= 32);
+                assert!(decode_tile_minimal(&header).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrips_custom_mesh_kinds() {
+        assert_eq!(
+            MeshKind::from_code(201).expect("custom code"),
+            MeshKind::Custom(201)
+        );
+        assert_eq!(MeshKind::Custom(255).code(), 255);
+        let error = MeshKind::from_code(3).expect_err("unassigned code should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let input = TileEncodeInput {
+            tile_id: u64::MAX,
+            mesh_kind: MeshKind::Custom(201),
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let encoded = encode_tile(input.clone()).expect("encode custom tile");
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode custom tile");
+        assert_eq!(decoded.header.mesh_kind, MeshKind::Custom(201));
+        assert_eq!(decoded.header.tile_id, u64::MAX);
+        assert!(decoded.header.describe().contains(" custom-201 "));
+
+        let error = encode_tile(TileEncodeInput {
+            mesh_kind: MeshKind::Custom(2),
+            ..input
+        })
+        .expect_err("custom kinds below the reserved range should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn compares_payloads_across_compression_modes() {
+        let deflated = decoded_tile(DType::Int16, tile_dims(), &[1.0, -2.0, 3.0, -4.0], None);
+        let stored = deflated
+            .resample_window((0.0, 0.0, 2.0, 2.0), 2, 2)
+            .expect("identity resample");
+        assert_eq!(stored.header.compression, CompressionMode::None);
+        assert_ne!(stored, deflated);
+        assert!(stored.payload_eq(&deflated));
+
+        let other = decoded_tile(DType::Int16, tile_dims(), &[1.0, -2.0, 3.0, 5.0], None);
+        assert!(!other.payload_eq(&deflated));
+    }
+
+    #[test]
+    fn decodes_selected_bands() {
+        let dims = TileDimensions {
+            rows: 1,
+            cols: 2,
+            bands: 4,
+        };
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let tile = decoded_tile(DType::Uint16, dims, &values, None);
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 9,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: dims,
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+
+        let gray = decode_tile_bands(&encoded.bytes, &[0]).expect("decode band 0");
+        assert_eq!(gray.header.dimensions.bands, 1);
+        assert_eq!(gray.header.payload_crc32, crc32fast::hash(&gray.payload));
+        assert_eq!(tile_values(&gray), vec![Some(1.0), Some(5.0)]);
+
+        let swapped = decode_tile_bands(&encoded.bytes, &[3, 1]).expect("decode bands 3, 1");
+        assert_eq!(
+            tile_values(&swapped),
+            vec![Some(4.0), Some(2.0), Some(8.0), Some(6.0)]
+        );
+
+        let error = decode_tile_bands(&encoded.bytes, &[4]).expect_err("band out of range");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn payload_length_error_names_dimensions_and_dtype() {
+        let error = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[0_u8; 6],
+            extensions: ExtensionOptions::default(),
+        })
+        .expect_err("short payload should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+        assert!(error
+            .message
+            .contains("expected 2x2x1 uint16 = 8 bytes, got 6"));
+    }
+
+    #[test]
+    fn encodes_tiles_to_writer() {
+        let tile = decoded_tile(DType::Int16, tile_dims(), &[1.0, -2.0, 3.0, -9999.0], None);
+        for (compression, sparse) in [
+            (CompressionMode::None, false),
+            (CompressionMode::DeflateRaw, false),
+            (CompressionMode::None, true),
+        ] {
+            let input = TileEncodeInput {
+                tile_id: 3,
+                mesh_kind: MeshKind::JisX0410,
+                dtype: DType::Int16,
+                endianness: Endianness::Little,
+                compression,
+                dimensions: tile_dims(),
+                no_data: Some(-9999.0),
+                payload: &tile.payload,
+                extensions: ExtensionOptions {
+                    sparse,
+                    ..ExtensionOptions::default()
+                },
+            };
+            let mut out = Vec::new();
+            let header = encode_tile_to_writer(&mut out, input.clone()).expect("encode to writer");
+            let encoded = encode_tile(input).expect("encode tile");
+            assert_eq!(out, encoded.bytes);
+            assert_eq!(header, encoded.header);
+        }
+    }
+
+    #[test]
+    fn crc32_backend_matches_reference_checksum() {
+        assert_eq!(checksum::crc32(b"123456789"), 0xcbf4_3926);
+
+        let mut hasher = checksum::Crc32Hasher::new();
+        hasher.update(b"1234");
+        hasher.update(b"56789");
+        assert_eq!(hasher.finalize(), crc32fast::hash(b"123456789"));
+    }
+
+    #[test]
+    fn reads_integrity_fields_from_header() {
+        let tile = decoded_tile(DType::Uint16, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 533_945,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+
+        let fields = read_integrity_fields(&encoded.bytes).expect("read integrity fields");
+        assert_eq!(
+            fields,
+            IntegrityFields {
+                tile_id: 533_945,
+                payload_crc32: encoded.header.payload_crc32,
+                uncompressed_bytes: encoded.header.payload_uncompressed_bytes,
+                compressed_bytes: encoded.header.payload_compressed_bytes,
+            }
+        );
+
+        let mut corrupted = encoded.bytes;
+        corrupted[OFFSET_TILE_ID] ^= 1;
+        let error = read_integrity_fields(&corrupted).expect_err("header checksum should fail");
+        assert_eq!(error.code, TileErrorCode::HeaderChecksumMismatch);
+    }
+
+    #[test]
+    fn roundtrips_metadata_in_extension_trailer() {
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let metadata = vec![
+            (String::from("source"), String::from("granule-0042")),
+            (String::from("version"), String::from("処理 v2")),
+        ];
+        let input = TileEncodeInput {
+            tile_id: 11,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions {
+                metadata: &metadata,
+                ..ExtensionOptions::default()
+            },
+        };
+        let encoded = encode_tile(input.clone()).expect("encode tile with metadata");
+        let plain = encode_tile(TileEncodeInput {
+            extensions: ExtensionOptions::default(),
+            ..input.clone()
+        })
+        .expect("encode tile");
+        assert_eq!(encoded.header.payload_crc32, plain.header.payload_crc32);
+        assert_eq!(encoded.bytes[..plain.bytes.len()], plain.bytes[..]);
+
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode tile");
+        assert_eq!(decoded.header.metadata, metadata);
+        assert_eq!(decoded.payload, tile.payload);
+        assert!(decode_tile_minimal(&plain.bytes)
+            .expect("decode plain tile")
+            .header
+            .metadata
+            .is_empty());
+
+        let oversized = vec![(String::from("blob"), "x".repeat(64 * 1024))];
+        let error = encode_tile(TileEncodeInput {
+            extensions: ExtensionOptions {
+                metadata: &oversized,
+                ..input.extensions
+            },
+            ..input
+        })
+        .expect_err("oversized metadata should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn rejects_corrupt_or_duplicate_extension_records() {
+        let payload = [1_u8, 2, 3, 4];
+        let plain = encode_tile(TileEncodeInput {
+            tile_id: 11,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        // Two format-minor records: tag, u32 length, 1-byte value each.
+        let body = [0x04, 1, 0, 0, 0, 1, 0x04, 1, 0, 0, 0, 2];
+        let mut duplicated = plain.bytes.clone();
+        duplicated.extend_from_slice(b"MTX1");
+        duplicated.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        duplicated.extend_from_slice(&body);
+        duplicated.extend_from_slice(&crate::checksum::crc32(&body).to_le_bytes());
+        let error = decode_tile_minimal(&duplicated).expect_err("duplicate tag should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        assert!(error.message.contains("0x04"), "{}", error.message);
+
+        let mut corrupt = duplicated;
+        corrupt[TILE_FIXED_HEADER_LENGTH + payload.len() + 8 + 5] ^= 1;
+        let error = decode_tile_minimal(&corrupt).expect_err("corrupt trailer should fail");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+        assert!(error.message.contains("trailer"), "{}", error.message);
+    }
+
+    #[test]
+    fn evaluates_compression_modes_without_encoding_a_tile() {
+        let payload = vec![7_u8; 4096];
+        let reports = evaluate_compression(
+            &payload,
+            &[CompressionMode::DeflateRaw, CompressionMode::None],
+        )
+        .expect("evaluate compression");
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].mode, CompressionMode::DeflateRaw);
+        assert!(reports[0].compressed_bytes < payload.len());
+        assert_eq!(reports[1].mode, CompressionMode::None);
+        assert_eq!(reports[1].compressed_bytes, payload.len());
+    }
+
+    #[test]
+    fn flags_payloads_that_are_compressed_streams() {
+        let values: Vec<f64> = (0..256).map(|v| f64::from(v % 7)).collect();
+        let dims = TileDimensions {
+            rows: 16,
+            cols: 16,
+            bands: 1,
+        };
+        let tile = decoded_tile(DType::Uint8, dims, &values, None);
+        assert!(!tile.looks_double_compressed());
+
+        let inner = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: dims,
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode inner tile");
+        let stream = &inner.bytes[TILE_FIXED_HEADER_LENGTH..];
+        let stream_values: Vec<f64> = stream.iter().map(|&b| f64::from(b)).collect();
+        let doubled = decoded_tile(
+            DType::Uint8,
+            TileDimensions {
+                rows: 1,
+                cols: stream.len() as u32,
+                bands: 1,
+            },
+            &stream_values,
+            None,
+        );
+        assert!(doubled.looks_double_compressed());
+
+        // A stream that expands far past the probe cap is flagged without full inflation.
+        let mut seed = 1_u32;
+        let mut expanding: Vec<u8> = (0..2048)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 24) as u8
+            })
+            .collect();
+        expanding.resize(2048 + (1 << 20), 0);
+        let bomb = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: TileDimensions {
+                rows: 1,
+                cols: expanding.len() as u32,
+                bands: 1,
+            },
+            no_data: None,
+            payload: &expanding,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode expanding tile");
+        let stream_len = bomb.header.payload_compressed_bytes as usize;
+        let stream = &bomb.bytes[TILE_FIXED_HEADER_LENGTH..TILE_FIXED_HEADER_LENGTH + stream_len];
+        assert!(stream.len() * 16 < expanding.len());
+        let stream_values: Vec<f64> = stream.iter().map(|&b| f64::from(b)).collect();
+        let doubled = decoded_tile(
+            DType::Uint8,
+            TileDimensions {
+                rows: 1,
+                cols: stream.len() as u32,
+                bands: 1,
+            },
+            &stream_values,
+            None,
+        );
+        assert!(doubled.looks_double_compressed());
+    }
+
+    #[test]
+    fn rejects_undefined_compression_codes_as_invalid() {
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let mut forged = encoded.bytes;
+        forged[OFFSET_COMPRESSION] = 9;
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+
+        let error = decode_tile_minimal(&forged).expect_err("undefined code should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        assert!(error.message.contains("compression code 9"));
+    }
+
+    #[test]
+    fn validates_encode_input_without_encoding() {
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: Some(255.0),
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions {
+                sparse: true,
+                ..ExtensionOptions::default()
+            },
+        };
+        input.validate().expect("valid input");
+
+        let cases = [
+            (
+                TileEncodeInput {
+                    payload: &[1, 2, 3],
+                    ..input.clone()
+                },
+                TileErrorCode::InvalidPayloadLength,
+            ),
+            (
+                TileEncodeInput {
+                    no_data: Some(256.0),
+                    ..input.clone()
+                },
+                TileErrorCode::InvalidFieldValue,
+            ),
+            (
+                TileEncodeInput {
+                    no_data: None,
+                    ..input.clone()
+                },
+                TileErrorCode::MissingRequiredField,
+            ),
+            (
+                TileEncodeInput {
+                    mesh_kind: MeshKind::Xyz,
+                    tile_id: u64::MAX,
+                    ..input.clone()
+                },
+                TileErrorCode::InvalidFieldValue,
+            ),
+        ];
+        for (case, code) in cases {
+            assert_eq!(case.validate().expect_err("invalid input").code, code);
+            assert_eq!(encode_tile(case).expect_err("invalid input").code, code);
+        }
+    }
+
+    #[test]
+    fn rejects_tile_lengths_that_overflow_addressing() {
+        assert_eq!(
+            encoder::tile_length(10, 12).expect("small tile"),
+            TILE_FIXED_HEADER_LENGTH + 22
+        );
+        for (payload_len, extension_len) in [
+            (usize::MAX, 0),
+            (usize::MAX - TILE_FIXED_HEADER_LENGTH + 1, 0),
+            (usize::MAX - TILE_FIXED_HEADER_LENGTH, 1),
+        ] {
+            let error = encoder::tile_length(payload_len, extension_len)
+                .expect_err("overflowing length should fail");
+            assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+        }
+    }
+
+    #[test]
+    fn sorts_jis_tile_ids_by_level_then_row_major() {
+        let mut ids = vec![
+            5440, 533945, 5340, 53394511, 5339, 0, 12345, 53394500, 533946,
+        ];
+        ids.sort_by_key(|&id| tile_id_sort_key(id, MeshKind::JisX0410));
+        assert_eq!(
+            ids,
+            vec![0, 5339, 5340, 5440, 533945, 533946, 53394500, 53394511, 12345]
+        );
+
+        // The 1st mesh north of 5339 comes after every other mesh in its row.
+        assert!(
+            tile_id_sort_key(5399, MeshKind::JisX0410) < tile_id_sort_key(5439, MeshKind::JisX0410)
+        );
+        let xyz = (3_u64 << 58) | 27;
+        assert_eq!(tile_id_sort_key(xyz, MeshKind::Xyz), xyz);
+    }
+
+    #[test]
+    fn refreshes_payload_checksum_after_in_place_edit() {
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: Some(0.0),
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions::default(),
+        };
+        let mut bytes = encode_tile(input.clone()).expect("encode tile").bytes;
+        bytes[TILE_FIXED_HEADER_LENGTH + 2] = 0;
+        let error = decode_tile_minimal(&bytes).expect_err("stale checksum should fail");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+
+        let payload_crc32 = refresh_payload_checksum(&mut bytes).expect("refresh checksum");
+        assert_eq!(payload_crc32, crc32fast::hash(&[1, 2, 0, 4]));
+        let decoded = decode_tile_minimal(&bytes).expect("decode edited tile");
+        assert_eq!(
+            tile_values(&decoded),
+            vec![Some(1.0), Some(2.0), None, Some(4.0)]
+        );
+
+        let mut deflated = encode_tile(TileEncodeInput {
+            compression: CompressionMode::DeflateRaw,
+            ..input
+        })
+        .expect("encode tile")
+        .bytes;
+        let error = refresh_payload_checksum(&mut deflated).expect_err("compressed tile");
+        assert_eq!(error.code, TileErrorCode::UnsupportedCompression);
+
+        let mut with_adler32 = encode_tile(TileEncodeInput {
+            extensions: ExtensionOptions {
+                payload_adler32: true,
+                ..input.extensions
+            },
+            ..input
+        })
+        .expect("encode tile")
+        .bytes;
+        with_adler32[TILE_FIXED_HEADER_LENGTH] = 9;
+        refresh_payload_checksum(&mut with_adler32).expect("refresh checksums");
+        let decoded = decode_tile_minimal(&with_adler32).expect("decode edited tile");
+        assert_eq!(decoded.payload, [9, 2, 3, 4]);
+        assert_eq!(
+            decoded.header.payload_adler32,
+            Some(crate::checksum::adler32(&[9, 2, 3, 4]))
+        );
+
+        let two_bands = TileDimensions {
+            rows: 1,
+            cols: 2,
+            bands: 2,
+        };
+        let mut with_band_crc32 = encode_tile(TileEncodeInput {
+            dimensions: two_bands,
+            extensions: ExtensionOptions {
+                band_crc32: true,
+                ..input.extensions
+            },
+            ..input
+        })
+        .expect("encode tile")
+        .bytes;
+        with_band_crc32[TILE_FIXED_HEADER_LENGTH] = 7;
+        refresh_payload_checksum(&mut with_band_crc32).expect("refresh checksums");
+        for band in 0..2 {
+            decode_tile_bands(&with_band_crc32, &[band]).expect("band CRC matches");
+        }
+        assert_eq!(
+            decode_tile_minimal(&with_band_crc32)
+                .expect("decode")
+                .payload,
+            [7, 2, 3, 4]
+        );
+
+        let mut sparse_band_crc32 = encode_tile(TileEncodeInput {
+            dimensions: two_bands,
+            payload: &[0, 0, 0, 4],
+            extensions: ExtensionOptions {
+                sparse: true,
+                band_crc32: true,
+                ..input.extensions
+            },
+            ..input
+        })
+        .expect("encode tile")
+        .bytes;
+        assert!(inspect_tile(&sparse_band_crc32).unwrap().sparse);
+        let error = refresh_payload_checksum(&mut sparse_band_crc32)
+            .expect_err("sparse tile with band CRCs");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn decodes_uncompressed_tiles_without_copying_bytes() {
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions::default(),
+        };
+        let buf = bytes::Bytes::from(encode_tile(input.clone()).expect("encode tile").bytes);
+        let decoded = decode_tile_bytes(buf.clone()).expect("decode shared tile");
+        assert_eq!(&decoded.payload[..], &[1, 2, 3, 4]);
+        assert_eq!(
+            decoded.payload.as_ptr(),
+            buf[TILE_FIXED_HEADER_LENGTH..].as_ptr()
+        );
+
+        let deflated = encode_tile(TileEncodeInput {
+            compression: CompressionMode::DeflateRaw,
+            ..input
+        })
+        .expect("encode tile");
+        let decoded =
+            decode_tile_bytes(bytes::Bytes::from(deflated.bytes)).expect("decode deflated tile");
+        assert_eq!(&decoded.payload[..], &[1, 2, 3, 4]);
+        assert_eq!(decoded.header, deflated.header);
+    }
+
+    #[test]
+    fn v1_headers_have_no_reserved_bytes() {
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        assert!(inspect_tile_reserved(&encoded.bytes)
+            .expect("inspect reserved bytes")
+            .is_empty());
+        assert!(inspect_tile_reserved(&encoded.bytes[..10]).is_err());
+    }
+
+    #[test]
+    fn walks_xyz_parent_and_children() {
+        let xyz = |zoom: u64, quadkey: u64| (zoom << 58) | quadkey;
+        let tile = xyz(2, 0b10_01);
+        assert_eq!(xyz_parent(tile).expect("parent"), Some(xyz(1, 0b10)));
+        assert_eq!(xyz_parent(xyz(0, 0)).expect("root parent"), None);
+
+        let children = xyz_children(tile).expect("children");
+        assert_eq!(
+            children,
+            vec![
+                xyz(3, 0b10_01_00),
+                xyz(3, 0b10_01_01),
+                xyz(3, 0b10_01_10),
+                xyz(3, 0b10_01_11)
+            ]
+        );
+        for child in children {
+            assert_eq!(xyz_parent(child).expect("child parent"), Some(tile));
+        }
+        assert!(xyz_children(xyz(29, 7)).expect("max zoom").is_empty());
+
+        let error = xyz_parent(xyz(1, 4)).expect_err("invalid quadkey");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn compares_tiles_structurally() {
+        let metadata = vec![(String::from("k"), String::from("v"))];
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions::default(),
+        };
+        let a = encode_tile(input.clone()).expect("encode tile").bytes;
+
+        // Same header fields and payload, but a different deflate stream and length.
+        let mut b = a[..TILE_FIXED_HEADER_LENGTH].to_vec();
+        let stored = [0x01, 0x04, 0x00, 0xfb, 0xff, 1, 2, 3, 4];
+        b.extend_from_slice(&stored);
+        b[OFFSET_COMPRESSED_PAYLOAD_LENGTH..OFFSET_COMPRESSED_PAYLOAD_LENGTH + 8]
+            .copy_from_slice(&(stored.len() as u64).to_le_bytes());
+        let header_crc32 = crc32fast::hash(&b[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        b[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+        assert_ne!(a, b);
+        assert!(tiles_structurally_equal(&a, &b).expect("compare tiles"));
+
+        let tagged = encode_tile(TileEncodeInput {
+            extensions: ExtensionOptions {
+                metadata: &metadata,
+                ..input.extensions
+            },
+            ..input.clone()
+        })
+        .expect("encode tile")
+        .bytes;
+        assert!(!tiles_structurally_equal(&a, &tagged).expect("compare tiles"));
+        let other = encode_tile(TileEncodeInput {
+            payload: &[1, 2, 3, 5],
+            ..input
+        })
+        .expect("encode tile")
+        .bytes;
+        assert!(!tiles_structurally_equal(&a, &other).expect("compare tiles"));
+    }
+
+    #[test]
+    fn roundtrips_geotransform_and_maps_pixels() {
+        let geotransform = [139.0, 0.25, 0.0, 36.0, 0.0, -0.5];
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions {
+                geotransform: Some(geotransform),
+                ..ExtensionOptions::default()
+            },
+        };
+        let encoded = encode_tile(input.clone()).expect("encode tile");
+        let plain = encode_tile(TileEncodeInput {
+            extensions: ExtensionOptions::default(),
+            ..input.clone()
+        })
+        .expect("encode tile");
+        assert_eq!(encoded.header.payload_crc32, plain.header.payload_crc32);
+
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode tile");
+        assert_eq!(decoded.header.geotransform, Some(geotransform));
+        assert_eq!(decoded.pixel_to_world(1.0, 2.0), Some((139.5, 35.5)));
+        assert_eq!(
+            decode_tile_minimal(&plain.bytes)
+                .expect("decode tile")
+                .pixel_to_world(0.0, 0.0),
+            None
+        );
+
+        let window = decoded
+            .resample_window((1.0, 1.0, 2.0, 2.0), 2, 2)
+            .expect("resample window");
+        assert_eq!(window.pixel_to_world(0.0, 0.0), Some((139.25, 35.5)));
+        assert_eq!(
+            window.pixel_to_world(2.0, 2.0),
+            decoded.pixel_to_world(2.0, 2.0)
+        );
+
+        let error = encode_tile(TileEncodeInput {
+            extensions: ExtensionOptions {
+                geotransform: Some([f64::NAN, 1.0, 0.0, 0.0, 0.0, -1.0]),
+                ..input.extensions
+            },
+            ..input
+        })
+        .expect_err("non-finite geotransform should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn validates_xyz_tile_ids_at_zoom_boundaries() {
+        let xyz = |zoom: u64, quadkey: u64| (zoom << 58) | quadkey;
+        let check = |tile_id| common::validate_tile_id_for_mesh_kind(tile_id, MeshKind::Xyz);
+
+        check(xyz(0, 0)).expect("zoom 0 root");
+        check(xyz(0, 1)).expect_err("zoom 0 has a single tile");
+        check(xyz(1, 3)).expect("largest zoom 1 quadkey");
+        check(xyz(1, 4)).expect_err("zoom 1 quadkey out of range");
+        check(xyz(29, 0)).expect("smallest zoom 29 quadkey");
+        check(xyz(29, (1 << 58) - 1)).expect("largest zoom 29 quadkey");
+        for zoom in [30, 63] {
+            let error = check(xyz(zoom, 0)).expect_err("zoom above 29");
+            assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        }
+    }
+
+    #[test]
+    fn maps_values_into_a_new_dtype() {
+        let tile = decoded_tile(
+            DType::Float32,
+            tile_dims(),
+            &[0.5, 1.5, -9999.0, 300.0],
+            Some(-9999.0),
+        );
+        let mut calls = 0;
+        let mapped = tile
+            .map_values(DType::Int16, |v| {
+                calls += 1;
+                (v * 2.0).round()
+            })
+            .expect("map values");
+        assert_eq!(calls, 3);
+        assert_eq!(mapped.header.dtype, DType::Int16);
+        assert_eq!(
+            mapped.header.payload_crc32,
+            crc32fast::hash(&mapped.payload)
+        );
+        assert_eq!(
+            tile_values(&mapped),
+            vec![Some(1.0), Some(3.0), None, Some(600.0)]
+        );
+
+        let error = tile
+            .map_values(DType::Uint8, f64::round)
+            .expect_err("300 does not fit uint8");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn decodes_tile_and_returns_remainder() {
+        let metadata = vec![(String::from("k"), String::from("v"))];
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions {
+                metadata: &metadata,
+                ..ExtensionOptions::default()
+            },
+        };
+        let first = encode_tile(input.clone()).expect("encode first tile");
+        let second = encode_tile(TileEncodeInput {
+            tile_id: 2,
+            ..input
+        })
+        .expect("encode second tile");
+        let mut blob = first.bytes.clone();
+        blob.extend_from_slice(&second.bytes);
+
+        let (tile, rest) = decode_tile_with_remainder(&blob).expect("decode first tile");
+        assert_eq!(tile.header.tile_id, 1);
+        assert_eq!(rest, &second.bytes[..]);
+        let (tile, rest) = decode_tile_with_remainder(rest).expect("decode second tile");
+        assert_eq!(tile.header.tile_id, 2);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn masks_nan_samples_with_nan_no_data() {
+        let mut values = vec![f64::NAN; 16];
+        values[0] = 1.5;
+        values[9] = -2.0;
+        let mut payload = encode_payload_values(DType::Float32, Endianness::Little, &values)
+            .expect("encode payload values");
+        // A non-canonical NaN is still no_data and survives the sparse round trip.
+        payload[4..8].copy_from_slice(&0x7fc0_0001_u32.to_le_bytes());
+        let input = TileEncodeInput {
+            tile_id: 3,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Float32,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: TileDimensions {
+                rows: 4,
+                cols: 4,
+                bands: 1,
+            },
+            no_data: Some(f64::NAN),
+            payload: &payload,
+            extensions: ExtensionOptions {
+                sparse: true,
+                ..ExtensionOptions::default()
+            },
+        };
+        let encoded = encode_tile(input.clone()).expect("encode NaN no_data tile");
+        assert_eq!(encoded.header.no_data_kind, 2);
+        assert_eq!(encoded.header.no_data_value_raw, [0; 8]);
+        assert!(encoded.header.sparse);
+
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode NaN no_data tile");
+        assert!(decoded.header.no_data.is_some_and(f64::is_nan));
+        assert_eq!(decoded.header, encoded.header);
+        assert_eq!(decoded, decoded.clone());
+        assert_eq!(decoded.payload, payload);
+        let samples = decode_payload_values(
+            decoded.header.dtype,
+            decoded.header.endianness,
+            &decoded.payload,
+            decoded.header.no_data,
+        )
+        .expect("decode payload values");
+        let present: Vec<(usize, f64)> = samples
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, value)| value.map(|v| (idx, v)))
+            .collect();
+        assert_eq!(present, vec![(0, 1.5), (9, -2.0)]);
+        assert!(tiles_structurally_equal(&encoded.bytes, &encoded.bytes).expect("compare tiles"));
+        let numeric = encode_tile(TileEncodeInput {
+            no_data: Some(0.0),
+            ..input.clone()
+        })
+        .expect("encode numeric no_data tile");
+        assert_ne!(numeric.header, encoded.header);
+        assert!(!tiles_structurally_equal(&encoded.bytes, &numeric.bytes).expect("compare tiles"));
+
+        let error = encode_tile(TileEncodeInput {
+            dtype: DType::Int32,
+            payload: &[0; 64],
+            extensions: ExtensionOptions::default(),
+            ..input
+        })
+        .expect_err("NaN no_data on an integer dtype should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn estimates_payload_entropy() {
+        assert_eq!(estimate_entropy(&[]), 0.0);
+        assert_eq!(estimate_entropy(&[7; 4096]), 0.0);
+        let every_byte: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        assert_eq!(estimate_entropy(&every_byte), 8.0);
+        assert_eq!(estimate_entropy(&[0, 1, 0, 1]), 1.0);
+
+        let mut state = 0x2545_f491_u32;
+        let noise: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state.to_le_bytes()[0]
+            })
+            .collect();
+        assert!(!is_likely_compressible(&noise));
+        assert!(is_likely_compressible(&[7; 4096]));
+    }
+
+    #[test]
+    fn parses_header_from_fixed_header_bytes_only() {
+        let metadata = vec![(String::from("k"), String::from("v"))];
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 5,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions {
+                metadata: &metadata,
+                ..ExtensionOptions::default()
+            },
+        })
+        .expect("encode tile");
+        let head = &encoded.bytes[..TILE_FIXED_HEADER_LENGTH];
+
+        let header = parse_header_only(head).expect("parse header only");
+        assert_eq!(header.tile_id, 5);
+        assert_eq!(header.payload_crc32, encoded.header.payload_crc32);
+        assert!(header.metadata.is_empty());
+        assert_eq!(inspect_tile(head).expect("inspect header bytes"), header);
+        assert_eq!(
+            inspect_tile(&encoded.bytes).expect("inspect full tile"),
+            encoded.header
+        );
+
+        // Any cut inside the payload falls back to the fixed header alone.
+        let payload_end =
+            TILE_FIXED_HEADER_LENGTH + encoded.header.payload_compressed_bytes as usize;
+        for len in [TILE_FIXED_HEADER_LENGTH + 1, payload_end - 1] {
+            let partial = inspect_tile(&encoded.bytes[..len]).expect("inspect partial payload");
+            assert_eq!(partial, header);
+        }
+        let mut corrupt = head.to_vec();
+        corrupt[OFFSET_ROWS] ^= 1;
+        let error = parse_header_only(&corrupt).expect_err("corrupt header should fail");
+        assert_eq!(error.code, TileErrorCode::HeaderChecksumMismatch);
+    }
+
+    #[test]
+    fn rejects_no_data_not_exactly_representable_in_dtype() {
+        let payload = [0_u8; 32];
+        let input = |dtype: DType, no_data: f64| TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: Some(no_data),
+            payload: &payload[..4 * dtype.byte_size()],
+            extensions: ExtensionOptions::default(),
+        };
+
+        for (dtype, no_data) in [
+            (DType::Uint8, 256.0),
+            (DType::Int8, -129.0),
+            (DType::Uint16, 1.5),
+            (DType::Float32, 0.1),
+            (DType::Float32, 1e39),
+        ] {
+            let error = encode_tile(input(dtype, no_data)).expect_err("inexact no_data");
+            assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+            assert!(
+                error
+                    .message
+                    .starts_with(&format!("no_data {no_data} is not")),
+                "{}",
+                error.message
+            );
+        }
+        let error = encode_tile(input(DType::Float32, 0.1)).expect_err("inexact no_data");
+        assert!(error
+            .message
+            .contains("it would be stored as 0.10000000149011612"));
+
+        for (dtype, no_data) in [
+            (DType::Uint8, 255.0),
+            (DType::Float32, 0.5),
+            (DType::Float32, -9999.0),
+            (DType::Float64, 0.1),
+        ] {
+            encode_tile(input(dtype, no_data)).expect("exact no_data");
+        }
+    }
+
+    #[test]
+    fn streams_payload_values_without_collecting() {
+        let payload = encode_payload_values(DType::Int16, Endianness::Big, &[3.0, -1.0, 5.0, 9.0])
+            .expect("encode payload values");
+        let mut sum = 0.0;
+        for_each_payload_value(DType::Int16, Endianness::Big, &payload, |value| {
+            sum += value;
+            Ok(())
+        })
+        .expect("stream payload values");
+        assert_eq!(sum, 16.0);
+
+        let mut seen = 0;
+        let error = for_each_payload_value(DType::Int16, Endianness::Big, &payload, |value| {
+            seen += 1;
+            if value < 0.0 {
+                return Err(TileError::new(TileErrorCode::InvalidFieldValue, "negative"));
+            }
+            Ok(())
+        })
+        .expect_err("callback error should stop iteration");
+        assert_eq!(error.message, "negative");
+        assert_eq!(seen, 2);
+
+        let error =
+            for_each_payload_value(DType::Int16, Endianness::Big, &payload[..3], |_| Ok(()))
+                .expect_err("odd payload length should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+    }
+
+    #[test]
+    fn roundtrips_typed_xyz_tile_ids() {
+        let tile_id = XyzTileId::new(3, 5, 2).expect("valid XYZ tile");
+        assert_eq!((tile_id.zoom(), tile_id.x(), tile_id.y()), (3, 5, 2));
+        // x=101b, y=010b -> quadkey digits 1, 2, 1.
+        assert_eq!(tile_id.raw(), (3 << 58) | 0b01_10_01);
+        assert_eq!(XyzTileId::try_from(u64::from(tile_id)), Ok(tile_id));
+
+        let max = (1 << 29) - 1;
+        let corner = XyzTileId::new(29, max, max).expect("largest zoom 29 tile");
+        assert_eq!((corner.x(), corner.y()), (max, max));
+        assert_eq!(XyzTileId::new(0, 0, 0).expect("root tile").raw(), 0);
+
+        for (zoom, x, y) in [(30, 0, 0), (3, 8, 0), (3, 0, 8), (0, 1, 0)] {
+            let error = XyzTileId::new(zoom, x, y).expect_err("invalid XYZ tile");
+            assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        }
+        assert!(XyzTileId::try_from((1 << 58) | 4).is_err());
+    }
+
+    #[test]
+    fn verifies_requested_bands_with_band_crc32() {
+        let payload: Vec<u8> = (1..=12).collect();
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 8,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: TileDimensions {
+                rows: 2,
+                cols: 2,
+                bands: 3,
+            },
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions {
+                band_crc32: true,
+                payload_adler32: true,
+                ..ExtensionOptions::default()
+            },
+        })
+        .expect("encode tile with band CRCs");
+        let expected: Vec<u32> = (0..3)
+            .map(|band| {
+                let samples: Vec<u8> = payload.iter().skip(band).step_by(3).copied().collect();
+                crc32fast::hash(&samples)
+            })
+            .collect();
+        assert_eq!(encoded.header.band_crc32, Some(expected));
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode tile");
+        assert_eq!(decoded.header, encoded.header);
+
+        // Corrupt a band 1 sample: other bands still decode, since band CRCs replace the
+        // whole-payload CRC-32 and Adler-32; band 1 and the full tile fail.
+        let mut corrupt = encoded.bytes.clone();
+        corrupt[TILE_FIXED_HEADER_LENGTH + 4] ^= 0xff;
+        let narrowed = decode_tile_bands(&corrupt, &[2, 0]).expect("decode intact bands");
+        assert_eq!(narrowed.payload, vec![3, 1, 6, 4, 9, 7, 12, 10]);
+        let error = decode_tile_bands(&corrupt, &[1]).expect_err("corrupt band should fail");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+        let error = decode_tile_minimal(&corrupt).expect_err("corrupt payload should fail");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+    }
+
+    #[test]
+    fn rejects_uncompressed_tile_with_disagreeing_lengths() {
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 9,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+
+        // Forge a smaller uncompressed length and re-seal the header.
+        let mut forged = encoded.bytes.clone();
+        forged[OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH..OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH + 8]
+            .copy_from_slice(&2_u64.to_le_bytes());
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+
+        for error in [
+            inspect_tile(&forged).expect_err("inspect forged tile"),
+            parse_header_only(&forged[..TILE_FIXED_HEADER_LENGTH])
+                .expect_err("parse forged header"),
+            decode_tile_minimal(&forged).expect_err("decode forged tile"),
+        ] {
+            assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+            assert!(
+                error.message.contains("compression=none"),
+                "{}",
+                error.message
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn trusts_payload_crc_over_wrong_length_field() {
+        let payload = [1_u8, 2, 3, 4];
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 4,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode tile");
+        assert!(!decoded.has_wrong_length_field());
+
+        let mut forged = encoded.bytes.clone();
+        forged[OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH..OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH + 8]
+            .copy_from_slice(&7_u64.to_le_bytes());
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+
+        let error = decode_tile_minimal(&forged).expect_err("wrong length should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+
+        let trusting = DecodeOptions {
+            trust_payload_crc_over_length: true,
+            ..DecodeOptions::default()
+        };
+        let recovered = decode_tile_with_options(&forged, &trusting).expect("recover tile");
+        assert_eq!(recovered.payload, payload);
+        assert_eq!(recovered.header.payload_uncompressed_bytes, 7);
+        assert!(recovered.has_wrong_length_field());
+
+        // A wrong payload CRC is still fatal.
+        forged[OFFSET_PAYLOAD_CHECKSUM] ^= 0xff;
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+        let error = decode_tile_with_options(&forged, &trusting).expect_err("bad crc");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+    }
+
+    #[test]
+    fn applies_decode_options() {
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 4,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let decode = |bytes: &[u8], options: DecodeOptions| {
+            decode_tile_with_options(bytes, &options).map(|tile| tile.payload)
+        };
+
+        let mut bad_header_crc = encoded.bytes.clone();
+        bad_header_crc[HEADER_CHECKSUM_OFFSET] ^= 1;
+        let skip_header_crc = DecodeOptions {
+            verify_header_crc32: false,
+            ..DecodeOptions::default()
+        };
+        assert!(decode(&bad_header_crc, DecodeOptions::default()).is_err());
+        assert_eq!(
+            decode(&bad_header_crc, skip_header_crc),
+            Ok(tile.payload.clone())
+        );
+
+        let mut bad_payload_crc = encoded.bytes.clone();
+        bad_payload_crc[TILE_FIXED_HEADER_LENGTH] ^= 1;
+        let skip_payload_crc = DecodeOptions {
+            verify_payload_crc32: false,
+            ..DecodeOptions::default()
+        };
+        assert!(decode(&bad_payload_crc, DecodeOptions::default()).is_err());
+        assert_eq!(
+            decode(&bad_payload_crc, skip_payload_crc),
+            Ok(vec![0, 2, 3, 4])
+        );
+
+        let limited = |max| DecodeOptions {
+            max_uncompressed_bytes: Some(max),
+            ..DecodeOptions::default()
+        };
+        assert!(decode(&encoded.bytes, limited(4)).is_ok());
+        let error = decode(&encoded.bytes, limited(3)).expect_err("payload over the limit");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+
+        // A forged compressed length is rejected before the buffer length is consulted.
+        let mut forged = encoded.bytes.clone();
+        for offset in [
+            OFFSET_UNCOMPRESSED_PAYLOAD_LENGTH,
+            OFFSET_COMPRESSED_PAYLOAD_LENGTH,
+        ] {
+            forged[offset..offset + 8].copy_from_slice(&(1_u64 << 40).to_le_bytes());
+        }
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+        let compressed_limit = DecodeOptions {
+            max_compressed_bytes: Some(1 << 20),
+            ..DecodeOptions::default()
+        };
+        assert!(decode(&encoded.bytes, compressed_limit).is_ok());
+        let error = decode(&forged, compressed_limit).expect_err("forged length");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+        assert!(
+            error.message.contains("max_compressed_bytes"),
+            "{}",
+            error.message
+        );
+
+        let mut trailing = encoded.bytes.clone();
+        trailing.push(0);
+        let strict = DecodeOptions {
+            allow_trailing_bytes: false,
+            ..DecodeOptions::default()
+        };
+        assert!(decode(&trailing, DecodeOptions::default()).is_ok());
+        assert!(decode(&encoded.bytes, strict).is_ok());
+        let error = decode(&trailing, strict).expect_err("trailing byte should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+    }
+
+    #[test]
+    fn applies_encode_options() {
+        let repetitive = vec![3_u8; 64 * 64];
+        let mut state = 0x9e37_79b9_u32;
+        let noise: Vec<u8> = (0..64 * 64)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state.to_le_bytes()[0]
+            })
+            .collect();
+        let input = |payload| TileEncodeInput {
+            tile_id: 6,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: TileDimensions {
+                rows: 64,
+                cols: 64,
+                bands: 1,
+            },
+            no_data: None,
+            payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let auto = EncodeOptions {
+            compression_auto: true,
+            ..EncodeOptions::default()
+        };
+
+        let encoded = encode_tile_with_options(input(&noise), &auto).expect("encode noise");
+        assert_eq!(encoded.header.compression, CompressionMode::None);
+        assert_eq!(
+            decode_tile_minimal(&encoded.bytes)
+                .expect("decode noise")
+                .payload,
+            noise
+        );
+        let encoded =
+            encode_tile_with_options(input(&repetitive), &auto).expect("encode repetitive");
+        assert_eq!(encoded.header.compression, CompressionMode::DeflateRaw);
+
+        let stored = EncodeOptions {
+            compression_level: 0,
+            ..EncodeOptions::default()
+        };
+        let level0 = encode_tile_with_options(input(&repetitive), &stored).expect("level 0");
+        assert!(level0.header.payload_compressed_bytes > encoded.header.payload_compressed_bytes);
+        let error = encode_tile_with_options(
+            input(&repetitive),
+            &EncodeOptions {
+                compression_level: 10,
+                ..EncodeOptions::default()
+            },
+        )
+        .expect_err("level 10 should fail");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn deterministic_encoding_stores_payload_uncompressed() {
+        let payload = vec![5_u8; 4];
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let options = EncodeOptions {
+            deterministic: true,
+            ..EncodeOptions::default()
+        };
+        let first = encode_tile_with_options(input.clone(), &options).expect("encode");
+        let second = encode_tile_with_options(input, &options).expect("encode again");
+        assert_eq!(first.header.compression, CompressionMode::None);
+        assert_eq!(first.bytes, second.bytes);
+        assert_eq!(
+            decode_tile_minimal(&first.bytes).expect("decode").payload,
+            payload
+        );
+    }
+
+    #[test]
+    fn roundtrips_24_bit_integer_dtypes() {
+        let uint24 = [0.0, 1.0, 65_536.0, 16_777_215.0];
+        let int24 = [-8_388_608.0, -1.0, 0.0, 8_388_607.0];
+        for (dtype, values) in [(DType::Uint24, uint24), (DType::Int24, int24)] {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                let payload = encode_payload_values(dtype, endianness, &values)
+                    .expect("encode 24-bit values");
+                assert_eq!(payload.len(), 12);
+                let decoded = decode_payload_values(dtype, endianness, &payload, None)
+                    .expect("decode 24-bit values");
+                assert_eq!(decoded, values.map(Some).to_vec());
+            }
+
+            let tile = decoded_tile(dtype, tile_dims(), &values, Some(values[1]));
+            assert_eq!(tile.header.no_data, Some(values[1]));
+            let decoded = decode_payload_values(
+                dtype,
+                Endianness::Little,
+                &tile.payload,
+                tile.header.no_data,
+            )
+            .expect("decode 24-bit tile");
+            assert_eq!(decoded[1], None);
+        }
+
+        // Byte layout and sign extension: -2 is 0xfffffe.
+        let payload =
+            encode_payload_values(DType::Int24, Endianness::Big, &[-2.0, 0x123456 as f64])
+                .expect("encode int24 values");
+        assert_eq!(payload, [0xff, 0xff, 0xfe, 0x12, 0x34, 0x56]);
+        let payload =
+            encode_payload_values(DType::Int24, Endianness::Little, &[-2.0, 0x123456 as f64])
+                .expect("encode int24 values");
+        assert_eq!(payload, [0xfe, 0xff, 0xff, 0x56, 0x34, 0x12]);
+        assert_eq!(
+            decode_payload_values(DType::Int24, Endianness::Little, &payload, None),
+            Ok(vec![Some(-2.0), Some(0x123456 as f64)])
+        );
+
+        for (dtype, value) in [
+            (DType::Uint24, 16_777_216.0),
+            (DType::Uint24, -1.0),
+            (DType::Int24, 8_388_608.0),
+            (DType::Int24, -8_388_609.0),
+        ] {
+            for endianness in [Endianness::Little, Endianness::Big] {
+                let error = encode_payload_values(dtype, endianness, &[value])
+                    .expect_err("out-of-range 24-bit value");
+                assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+            }
+        }
+    }
+
+    #[cfg(feature = "hashing")]
+    #[test]
+    fn content_hash_ignores_storage_details() {
+        let values = [1.0, 2.0, 3.0, 4.0];
+        let deflated = decoded_tile(DType::Uint16, tile_dims(), &values, Some(0.0));
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 7,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: Some(0.0),
+            payload: &deflated.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode uncompressed tile");
+        let stored = decode_tile_minimal(&encoded.bytes).expect("decode uncompressed tile");
+        assert_ne!(stored.header, deflated.header);
+        assert_eq!(stored.content_hash(), deflated.content_hash());
+
+        let other_values =
+            decoded_tile(DType::Uint16, tile_dims(), &[1.0, 2.0, 3.0, 5.0], Some(0.0));
+        let other_no_data = decoded_tile(DType::Uint16, tile_dims(), &values, Some(9.0));
+        let other_dtype = decoded_tile(DType::Int16, tile_dims(), &values, Some(0.0));
+        for other in [other_values, other_no_data, other_dtype] {
+            assert_ne!(other.content_hash(), deflated.content_hash());
+        }
+    }
+
+    #[test]
+    fn short_inputs_fail_with_invalid_header_length() {
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        type Decode = fn(&[u8]) -> Result<()>;
+        let decoders: [(&str, Decode); 11] = [
+            ("decode_tile_minimal", |b| decode_tile_minimal(b).map(drop)),
+            ("decode_tile_with_options", |b| {
+                decode_tile_with_options(b, &DecodeOptions::default()).map(drop)
+            }),
+            ("decode_tile_with_remainder", |b| {
+                decode_tile_with_remainder(b).map(drop)
+            }),
+            ("decode_tile_bands", |b| {
+                decode_tile_bands(b, &[0]).map(drop)
+            }),
+            ("inspect_tile", |b| inspect_tile(b).map(drop)),
+            ("inspect_tile_reserved", |b| {
+                inspect_tile_reserved(b).map(drop)
+            }),
+            ("parse_header_only", |b| parse_header_only(b).map(drop)),
+            ("read_integrity_fields", |b| {
+                read_integrity_fields(b).map(drop)
+            }),
+            ("verify_identity", |b| verify_identity(b, 42, tile_dims())),
+            ("tiles_structurally_equal", |b| {
+                tiles_structurally_equal(b, b).map(drop)
+            }),
+            ("build_blob_index", |b| build_blob_index(b).map(drop)),
+        ];
+
+        for input in [
+            &[][..],
+            &b"MTI1"[..],
+            &encoded.bytes[..TILE_FIXED_HEADER_LENGTH - 1],
+        ] {
+            for (name, decode) in decoders {
+                if name == "build_blob_index" && input.is_empty() {
+                    // An empty blob is a valid blob with no tiles.
+                    continue;
+                }
+                let error = decode(input).expect_err(name);
+                assert_eq!(error.code, TileErrorCode::InvalidHeaderLength, "{name}");
+            }
+            assert!(!is_mesh_data_tile(input));
+            #[cfg(feature = "bytes")]
+            assert_eq!(
+                decode_tile_bytes(bytes::Bytes::copy_from_slice(input))
+                    .expect_err("decode_tile_bytes")
+                    .code,
+                TileErrorCode::InvalidHeaderLength
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_into_reused_buffer() {
+        let mut buf = Vec::with_capacity(64);
+        for compression in [CompressionMode::DeflateRaw, CompressionMode::None] {
+            for values in [[1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0]] {
+                let payload = encode_payload_values(DType::Uint8, Endianness::Little, &values)
+                    .expect("encode payload values");
+                let encoded = encode_tile(TileEncodeInput {
+                    tile_id: 2,
+                    mesh_kind: MeshKind::JisX0410,
+                    dtype: DType::Uint8,
+                    endianness: Endianness::Little,
+                    compression,
+                    dimensions: tile_dims(),
+                    no_data: None,
+                    payload: &payload,
+                    extensions: ExtensionOptions::default(),
+                })
+                .expect("encode tile");
+                let expected = decode_tile_minimal(&encoded.bytes).expect("decode tile");
+
+                let header = decode_tile_into(&encoded.bytes, &mut buf).expect("decode into");
+                assert_eq!(header, expected.header);
+                assert_eq!(buf, expected.payload);
+                assert!(buf.capacity() >= 64);
+
+                let mut corrupt = encoded.bytes.clone();
+                corrupt[HEADER_CHECKSUM_OFFSET] ^= 1;
+                let error = decode_tile_into(&corrupt, &mut buf).expect_err("corrupt header");
+                assert_eq!(error.code, TileErrorCode::HeaderChecksumMismatch);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrips_format_minor_version() {
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], None);
+        let input = TileEncodeInput {
+            tile_id: 3,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &tile.payload,
+            extensions: ExtensionOptions::default(),
+        };
+
+        // v1.0 tiles carry no trailer record, so existing tiles are unchanged.
+        let v1_0 = encode_tile(input.clone()).expect("encode v1.0 tile");
+        assert_eq!(v1_0.header.format_minor, TILE_VERSION_MINOR);
+        assert_eq!(v1_0.bytes.len(), TILE_FIXED_HEADER_LENGTH + 4);
+
+        let options = EncodeOptions {
+            format_minor: 3,
+            ..EncodeOptions::default()
+        };
+        let v1_3 = encode_tile_with_options(input, &options).expect("encode v1.3 tile");
+        let decoded = decode_tile_minimal(&v1_3.bytes).expect("decode v1.3 tile");
+        assert_eq!(decoded.header.format_minor, 3);
+        assert_eq!(decoded.header, v1_3.header);
+        assert_eq!(decoded.payload, tile.payload);
+        assert!(decoded.header.describe().starts_with("MTI1 v1.3 tile=3 "));
+    }
+
+    #[test]
+    fn estimates_decode_memory_from_header() {
+        let mut values = vec![0.0; 64];
+        values[3] = 9.0;
+        let payload = encode_payload_values(DType::Uint16, Endianness::Little, &values)
+            .expect("encode payload values");
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: TileDimensions {
+                rows: 8,
+                cols: 8,
+                bands: 1,
+            },
+            no_data: Some(0.0),
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+
+        let plain = encode_tile(input.clone()).expect("encode uncompressed tile");
+        assert_eq!(plain.header.decode_memory_estimate(), 128);
+
+        let deflated = encode_tile(TileEncodeInput {
+            compression: CompressionMode::DeflateRaw,
+            ..input.clone()
+        })
+        .expect("encode compressed tile");
+        assert_eq!(
+            deflated.header.decode_memory_estimate(),
+            128 + deflated.header.payload_compressed_bytes
+        );
+
+        // 8-byte bitmask + one stored sample, expanded to the 128-byte dense payload.
+        let sparse = encode_tile(TileEncodeInput {
+            extensions: ExtensionOptions {
+                sparse: true,
+                ..input.extensions
+            },
+            ..input
+        })
+        .expect("encode sparse tile");
+        assert_eq!(sparse.header.decode_memory_estimate(), 10 + 128);
+
+        let mut huge = plain.header;
+        huge.payload_uncompressed_bytes = u64::MAX;
+        huge.compression = CompressionMode::DeflateRaw;
+        assert_eq!(huge.decode_memory_estimate(), u64::MAX);
+    }
+
+    #[test]
+    fn roundtrips_float64_no_data_bits_in_both_endiannesses() {
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let payload = encode_payload_values(DType::Float64, endianness, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+            for no_data in [-9999.0, f64::MIN, f64::MAX, 1e300, -0.0, f64::MIN_POSITIVE] {
+                let encoded = encode_tile(TileEncodeInput {
+                    tile_id: 1,
+                    mesh_kind: MeshKind::JisX0410,
+                    dtype: DType::Float64,
+                    endianness,
+                    compression: CompressionMode::None,
+                    dimensions: tile_dims(),
+                    no_data: Some(no_data),
+                    payload: &payload,
+                    extensions: ExtensionOptions::default(),
+                })
+                .expect("encode float64 no_data");
+                // The value fills the whole 8-byte slot, so there is no padding either way.
+                let expected_raw = match endianness {
+                    Endianness::Little => no_data.to_le_bytes(),
+                    Endianness::Big => no_data.to_be_bytes(),
+                };
+                let decoded = decode_tile_minimal(&encoded.bytes).expect("decode float64 tile");
+                assert_eq!(decoded.header.no_data_kind, 1);
+                assert_eq!(decoded.header.no_data_value_raw, expected_raw);
+                assert_eq!(
+                    decoded.header.no_data.map(f64::to_bits),
+                    Some(no_data.to_bits()),
+                    "{no_data} {endianness:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mosaics_and_downsamples_four_children() {
+        let child = |x: u32, y: u32, values: &[f64], geotransform: Option<[f64; 6]>| {
+            let payload = encode_payload_values(DType::Uint16, Endianness::Little, values)
+                .expect("encode payload values");
+            encode_tile(TileEncodeInput {
+                tile_id: XyzTileId::new(1, x, y).expect("valid xyz tile").raw(),
+                mesh_kind: MeshKind::Xyz,
+                dtype: DType::Uint16,
+                endianness: Endianness::Little,
+                compression: CompressionMode::DeflateRaw,
+                dimensions: tile_dims(),
+                no_data: Some(65535.0),
+                payload: &payload,
+                extensions: ExtensionOptions {
+                    geotransform,
+                    ..ExtensionOptions::default()
+                },
+            })
+            .expect("encode child")
+            .bytes
+        };
+        let top_left = child(0, 0, &[1.0, 2.0, 3.0, 4.0], None);
+        let top_right = child(1, 0, &[10.0, 10.0, 10.0, 65535.0], None);
+        let bottom_right = child(1, 1, &[65535.0; 4], None);
+
+        let parent =
+            mosaic_2x2_downsample([Some(&top_left), Some(&top_right), None, Some(&bottom_right)])
+                .expect("mosaic children");
+        assert_eq!(parent.header.tile_id, 0);
+        assert_eq!(parent.header.dimensions, tile_dims());
+        let decoded = decode_tile_minimal(&parent.bytes).expect("decode parent");
+        let values = decode_payload_values(
+            DType::Uint16,
+            Endianness::Little,
+            &decoded.payload,
+            decoded.header.no_data,
+        )
+        .expect("decode parent values");
+        // 2.5 rounds away from zero; no_data samples and missing children are skipped.
+        assert_eq!(values, vec![Some(3.0), Some(10.0), None, None]);
+
+        // The geotransform is moved to the mosaic's top-left corner at half resolution.
+        let only_top_right = child(1, 0, &[7.0; 4], Some([100.0, 1.0, 0.0, 50.0, 0.0, -1.0]));
+        let parent = mosaic_2x2_downsample([None, Some(&only_top_right), None, None])
+            .expect("mosaic single child");
+        assert_eq!(
+            parent.header.geotransform,
+            Some([98.0, 2.0, 0.0, 50.0, 0.0, -2.0])
+        );
+
+        let err = mosaic_2x2_downsample([Some(&top_right), None, None, None])
+            .expect_err("child in the wrong slot");
+        assert_eq!(err.code, TileErrorCode::InvalidFieldValue);
+        let err = mosaic_2x2_downsample([None; 4]).expect_err("no children");
+        assert_eq!(err.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[test]
+    fn indexes_samples_in_row_major_order() {
+        let dims = TileDimensions {
+            rows: 2,
+            cols: 3,
+            bands: 2,
+        };
+        // Each sample holds `row * 100 + col * 10 + band`.
+        let mut values = Vec::new();
+        for row in 0..2 {
+            for col in 0..3 {
+                for band in 0..2 {
+                    values.push(f64::from(row * 100 + col * 10 + band));
+                }
+            }
+        }
+        let tile = decoded_tile(DType::Uint16, dims, &values, None);
+        assert_eq!(tile.header.shape(), (2, 3, 2));
+
+        for row in 0..2 {
+            for col in 0..3 {
+                for band in 0..2_u8 {
+                    let index = tile.row_major_index(row, col, band).expect("in range");
+                    assert_eq!(
+                        values[index],
+                        f64::from(row * 100 + col * 10 + u32::from(band))
+                    );
+                }
+            }
+        }
+        assert_eq!(tile.row_major_index(1, 2, 1), Some(11));
+        assert_eq!(tile.row_major_index(2, 0, 0), None);
+        assert_eq!(tile.row_major_index(0, 3, 0), None);
+        assert_eq!(tile.row_major_index(0, 0, 2), None);
+
+        // `header` is public, so forged dimensions must not overflow the index.
+        let mut forged = tile;
+        forged.header.dimensions = TileDimensions {
+            rows: u32::MAX,
+            cols: u32::MAX,
+            bands: 255,
+        };
+        assert_eq!(
+            forged.row_major_index(u32::MAX - 1, u32::MAX - 1, 254),
+            None
+        );
+        assert_eq!(forged.row_major_index(0, 1, 0), Some(255));
+    }
+
+    #[test]
+    fn verifies_optional_payload_adler32() {
+        assert_eq!(crate::checksum::adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(crate::checksum::adler32(&[]), 1);
+
+        let payload = vec![0xab_u8; 20_000];
+        let dims = TileDimensions {
+            rows: 100,
+            cols: 100,
+            bands: 1,
+        };
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 7,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: dims,
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions {
+                payload_adler32: true,
+                ..ExtensionOptions::default()
+            },
+        })
+        .expect("encode with adler32");
+        let expected = crate::checksum::adler32(&payload);
+        assert_eq!(encoded.header.payload_adler32, Some(expected));
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode with adler32");
+        assert_eq!(decoded.header.payload_adler32, Some(expected));
+        assert_eq!(decoded.payload, payload);
+
+        // Store a wrong Adler-32 and reseal the trailer so only the Adler check can fail.
+        let mut bytes = encoded.bytes.clone();
+        let body_start = TILE_FIXED_HEADER_LENGTH + payload.len() + 8;
+        let body_end = bytes.len() - 4;
+        assert_eq!(bytes[body_start], 0x05);
+        bytes[body_start + 5] ^= 0x01;
+        let body_crc32 = crate::checksum::crc32(&bytes[body_start..body_end]);
+        bytes[body_end..].copy_from_slice(&body_crc32.to_le_bytes());
+        let err = decode_tile_minimal(&bytes).expect_err("adler32 mismatch");
+        assert_eq!(err.code, TileErrorCode::PayloadChecksumMismatch);
+        assert!(err.message.contains("Adler-32"), "{}", err.message);
+        let options = DecodeOptions {
+            verify_payload_crc32: false,
+            ..DecodeOptions::default()
+        };
+        decode_tile_with_options(&bytes, &options).expect("checks disabled");
+    }
+
+    #[test]
+    fn splits_bands_into_single_band_tiles() {
+        let dims = TileDimensions {
+            rows: 2,
+            cols: 2,
+            bands: 3,
+        };
+        // Pixel-interleaved: each pixel holds bands 0, 1, 2 in turn.
+        let values = [
+            1.0, 10.0, 100.0, 2.0, 20.0, 200.0, 3.0, 30.0, 300.0, 4.0, 40.0, 400.0,
+        ];
+        let tile = decoded_tile(DType::Uint16, dims, &values, Some(0.0));
+
+        let bands = tile.split_bands().expect("split bands");
+        assert_eq!(bands.len(), 3);
+        for (band, single) in bands.iter().enumerate() {
+            assert_eq!(single.header.dimensions.bands, 1);
+            assert_eq!(single.header.tile_id, tile.header.tile_id);
+            assert_eq!(single.header.dtype, DType::Uint16);
+            assert_eq!(single.header.no_data, Some(0.0));
+            assert_eq!(
+                single.header.payload_crc32,
+                crc32fast::hash(&single.payload)
+            );
+            let expected: Vec<Option<f64>> = values
+                .iter()
+                .skip(band)
+                .step_by(3)
+                .map(|&v| Some(v))
+                .collect();
+            let actual = decode_payload_values(
+                single.header.dtype,
+                single.header.endianness,
+                &single.payload,
+                single.header.no_data,
+            )
+            .expect("decode band values");
+            assert_eq!(actual, expected);
+
+            let reencoded = encode_tile(TileEncodeInput {
+                tile_id: single.header.tile_id,
+                mesh_kind: single.header.mesh_kind,
+                dtype: single.header.dtype,
+                endianness: single.header.endianness,
+                compression: CompressionMode::DeflateRaw,
+                dimensions: single.header.dimensions,
+                no_data: single.header.no_data,
+                payload: &single.payload,
+                extensions: ExtensionOptions::default(),
+            })
+            .expect("re-encode band");
+            let decoded = decode_tile_minimal(&reencoded.bytes).expect("decode band");
+            assert!(decoded.payload_eq(single));
+        }
+    }
+
+    #[test]
+    fn stacks_single_band_tiles() {
+        let dims = TileDimensions {
+            rows: 2,
+            cols: 2,
+            bands: 3,
+        };
+        let values = [
+            1.0, 10.0, 100.0, 2.0, 20.0, 200.0, 3.0, 30.0, 300.0, 4.0, 40.0, 400.0,
+        ];
+        let tile = decoded_tile(DType::Uint16, dims, &values, Some(0.0));
+        let bands = tile.split_bands().expect("split bands");
+
+        let stacked = stack_bands(&bands).expect("stack bands");
+        assert!(stacked.payload_eq(&tile));
+        assert_eq!(stacked.header.no_data, Some(0.0));
+        assert_eq!(
+            stacked.header.payload_crc32,
+            crc32fast::hash(&stacked.payload)
+        );
+
+        let mut other_dtype = bands.clone();
+        other_dtype[1] = bands[1].map_values(DType::Int32, |v| v).expect("map");
+        let mut other_no_data = bands.clone();
+        other_no_data[2].header.no_data = None;
+        let mut other_tile_id = bands.clone();
+        other_tile_id[0].header.tile_id = 43;
+        for invalid in [
+            &other_dtype[..],
+            &other_no_data[..],
+            &other_tile_id[..],
+            &[tile.clone()][..],
+            &[][..],
+        ] {
+            let err = stack_bands(invalid).expect_err("incompatible tiles");
+            assert_eq!(err.code, TileErrorCode::InvalidFieldValue);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reads_and_writes_tile_files() {
+        let dims = tile_dims();
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &[1.0, 2.0, 3.0, 4.0])
+                .expect("encode payload values");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: dims,
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+
+        let dir = std::env::temp_dir().join(format!("mesh-data-tile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        let path = dir.join("tile.bin");
+        write_tile_file(&path, &encoded).expect("write tile file");
+        let decoded = read_tile_file(&path).expect("read tile file");
+        assert_eq!(decoded.header, encoded.header);
+        assert_eq!(decoded.payload, payload);
+
+        let err = read_tile_file(dir.join("missing.bin")).expect_err("missing file");
+        assert_eq!(err.code, TileErrorCode::IoError);
+        let err = write_tile_file(dir.join("missing").join("tile.bin"), &encoded)
+            .expect_err("missing directory");
+        assert_eq!(err.code, TileErrorCode::IoError);
+        std::fs::remove_dir_all(&dir).expect("remove temp dir");
+    }
+
+    #[test]
+    fn diagnoses_uncompressed_payload_flagged_as_deflate() {
+        // 0x07 starts a deflate block with the reserved block type, so inflating fails.
+        let payload = [0x07_u8, 0x10, 0x20, 0x30];
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let relabel = |bytes: &mut [u8]| {
+            bytes[OFFSET_COMPRESSION] = CompressionMode::DeflateRaw.code();
+            let header_crc32 = crc32fast::hash(&bytes[..HEADER_CHECKSUM_INPUT_LENGTH]);
+            bytes[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+                .copy_from_slice(&header_crc32.to_le_bytes());
+        };
+
+        let mut mislabelled = encoded.bytes.clone();
+        relabel(&mut mislabelled);
+        let err = decode_tile_minimal(&mislabelled).expect_err("not a deflate stream");
+        assert_eq!(err.code, TileErrorCode::DecompressionFailed);
+        assert!(
+            err.message.contains("compression field is wrong"),
+            "{}",
+            err.message
+        );
+
+        // Bytes that match neither the deflate format nor the checksum get no hint.
+        let mut corrupt = mislabelled.clone();
+        corrupt[TILE_FIXED_HEADER_LENGTH + 1] ^= 0xff;
+        let err = decode_tile_minimal(&corrupt).expect_err("corrupt deflate stream");
+        assert_eq!(err.code, TileErrorCode::DecompressionFailed);
+        assert!(
+            !err.message.contains("compression field"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_no_data_for_every_integer_dtype() {
+        let payload = [0_u8; 16];
+        for (dtype, min, max) in [
+            (DType::Uint8, 0.0, f64::from(u8::MAX)),
+            (DType::Int8, f64::from(i8::MIN), f64::from(i8::MAX)),
+            (DType::Uint16, 0.0, f64::from(u16::MAX)),
+            (DType::Int16, f64::from(i16::MIN), f64::from(i16::MAX)),
+            (DType::Uint24, 0.0, 16_777_215.0),
+            (DType::Int24, -8_388_608.0, 8_388_607.0),
+            (DType::Uint32, 0.0, f64::from(u32::MAX)),
+            (DType::Int32, f64::from(i32::MIN), f64::from(i32::MAX)),
+        ] {
+            let input = |no_data: f64| TileEncodeInput {
+                tile_id: 1,
+                mesh_kind: MeshKind::JisX0410,
+                dtype,
+                endianness: Endianness::Big,
+                compression: CompressionMode::None,
+                dimensions: tile_dims(),
+                no_data: Some(no_data),
+                payload: &payload[..4 * dtype.byte_size()],
+                extensions: ExtensionOptions::default(),
+            };
+
+            for no_data in [
+                max + 1.0,
+                min - 1.0,
+                3.5,
+                -0.5,
+                max - 0.5,
+                f64::NAN,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ] {
+                let error = encode_tile(input(no_data))
+                    .expect_err(&format!("{dtype:?} no_data {no_data} must be rejected"));
+                assert_eq!(error.code, TileErrorCode::InvalidFieldValue, "{dtype:?}");
+            }
+            for no_data in [min, max, 0.0] {
+                let encoded = encode_tile(input(no_data))
+                    .unwrap_or_else(|err| panic!("{dtype:?} no_data {no_data}: {err:?}"));
+                assert_eq!(encoded.header.no_data, Some(no_data));
+            }
+        }
+    }
+
+    #[test]
+    fn wraps_precompressed_payload_without_recompressing() {
+        let values = [1.0, 2.0, 3.0, 4.0];
+        let payload = encode_payload_values(DType::Uint16, Endianness::Little, &values)
+            .expect("encode payload values");
+        let input = TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let original = encode_tile(input.clone()).expect("encode tile");
+        let stream_end =
+            TILE_FIXED_HEADER_LENGTH + original.header.payload_compressed_bytes as usize;
+        let stream = &original.bytes[TILE_FIXED_HEADER_LENGTH..stream_end];
+
+        let metadata = [(String::from("source"), String::from("cdn"))];
+        let rewrapped = encode_tile_precompressed(
+            TileEncodeInput {
+                tile_id: 43,
+                payload: stream,
+                extensions: ExtensionOptions {
+                    metadata: &metadata,
+                    ..input.extensions
+                },
+                ..input.clone()
+            },
+            original.header.payload_crc32,
+            payload.len(),
+        )
+        .expect("wrap precompressed payload");
+        assert_eq!(
+            &rewrapped.bytes[TILE_FIXED_HEADER_LENGTH..stream_end],
+            stream
+        );
+        let decoded = decode_tile_minimal(&rewrapped.bytes).expect("decode rewrapped tile");
+        assert_eq!(decoded.header, rewrapped.header);
+        assert_eq!(decoded.header.tile_id, 43);
+        assert_eq!(decoded.payload, payload);
+
+        let err = encode_tile_precompressed(
+            TileEncodeInput {
+                payload: stream,
+                ..input.clone()
+            },
+            original.header.payload_crc32,
+            payload.len() + 2,
+        )
+        .expect_err("declared length disagrees with dimensions");
+        assert_eq!(err.code, TileErrorCode::InvalidPayloadLength);
+        let err = encode_tile_precompressed(
+            TileEncodeInput {
+                payload: stream,
+                extensions: ExtensionOptions {
+                    band_crc32: true,
+                    ..input.extensions
+                },
+                ..input
+            },
+            original.header.payload_crc32,
+            payload.len(),
+        )
+        .expect_err("band CRCs need the uncompressed payload");
+        assert_eq!(err.code, TileErrorCode::InvalidFieldValue);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn computes_xyz_tile_bounds() {
+        let tile_id = XyzTileId::new(12, 3639, 1612).expect("valid tile").raw();
+        let bounds = xyz_tile_bounds(tile_id).expect("bounds");
+        let expected = TileBounds {
+            west: 139.833984375,
+            south: 35.67514743608467,
+            east: 139.921875,
+            north: 35.7465122599185,
+        };
+        for (actual, expected) in [
+            (bounds.west, expected.west),
+            (bounds.south, expected.south),
+            (bounds.east, expected.east),
+            (bounds.north, expected.north),
+        ] {
+            assert!((actual - expected).abs() < 1e-9, "{bounds:?}");
+        }
+
+        let world = xyz_tile_bounds(0).expect("zoom 0 bounds");
+        assert_eq!((world.west, world.east), (-180.0, 180.0));
+        assert!((world.north - 85.051_128_779_806_59).abs() < 1e-9);
+        assert!((world.south + 85.051_128_779_806_59).abs() < 1e-9);
+        assert!(xyz_tile_bounds(u64::MAX).is_err());
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn reports_payload_sizes_beyond_usize() {
+        let dims = |bands| TileDimensions {
+            rows: u32::MAX,
+            cols: u32::MAX,
+            bands,
+        };
+        // (2^32 - 1)^2 bytes is just below usize::MAX on 64-bit targets.
+        let len = expected_payload_length(dims(1), DType::Uint8).expect("fits in usize");
+        assert_eq!(len as u128, u128::from(u32::MAX) * u128::from(u32::MAX));
+
+        // Doubling it by sample size or by bands crosses usize::MAX.
+        for (bands, dtype, bytes) in [
+            (1, DType::Uint16, "36893488130239234050 bytes (32.0 EiB)"),
+            (2, DType::Uint8, "36893488130239234050 bytes (32.0 EiB)"),
+            (
+                255,
+                DType::Float64,
+                "37631357892844018731000 bytes (31.9 ZiB)",
+            ),
+        ] {
+            let err = expected_payload_length(dims(bands), dtype).expect_err("exceeds usize");
+            assert_eq!(err.code, TileErrorCode::InvalidPayloadLength);
+            assert!(err.message.contains(bytes), "{}", err.message);
+        }
+
+        assert_eq!(
+            dims(1).total_samples().expect("fits in u64"),
+            u64::from(u32::MAX) * u64::from(u32::MAX)
+        );
+        let err = dims(255).total_samples().expect_err("overflows u64");
+        assert_eq!(err.code, TileErrorCode::InvalidFieldValue);
+        assert!(
+            err.message.contains("4703919736605502341375 samples"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn roundtrips_palette_for_categorical_tiles() {
+        let palette = [[0, 0, 0, 0], [34, 139, 34, 255], [30, 144, 255, 255]];
+        let payload = [0_u8, 1, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let input = |dtype: DType, bands: u8, palette| TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: TileDimensions {
+                rows: 2,
+                cols: 2,
+                bands,
+            },
+            no_data: None,
+            payload: &payload[..4 * usize::from(bands) * dtype.byte_size()],
+            extensions: ExtensionOptions {
+                palette: Some(palette),
+                ..ExtensionOptions::default()
+            },
+        };
+
+        let encoded = encode_tile(input(DType::Uint8, 1, &palette)).expect("encode palette");
+        assert_eq!(encoded.header.payload_crc32, crc32fast::hash(&payload[..4]));
+        let decoded = decode_tile_minimal(&encoded.bytes).expect("decode palette");
+        assert_eq!(decoded.header.palette.as_deref(), Some(&palette[..]));
+        assert_eq!(decoded.header, encoded.header);
+        let floats = decoded
+            .map_values(DType::Float32, |v| v)
+            .expect("map to float");
+        assert_eq!(floats.header.palette, None);
+
+        let too_many = [[0_u8; 4]; 257];
+        for invalid in [
+            input(DType::Float32, 1, &palette),
+            input(DType::Uint8, 2, &palette),
+            input(DType::Uint8, 1, &too_many),
+            input(DType::Uint8, 1, &[]),
+        ] {
+            let err = encode_tile(invalid).expect_err("invalid palette");
+            assert_eq!(err.code, TileErrorCode::InvalidFieldValue);
+        }
+    }
+
+    #[test]
+    fn checks_decoded_tile_invariants() {
+        let tile = decoded_tile(DType::Uint8, tile_dims(), &[1.0, 2.0, 3.0, 4.0], Some(0.0));
+        tile.assert_invariants().expect("decoded tile is valid");
+
+        let mut short_payload = tile.clone();
+        short_payload.payload.pop();
+        let mut no_data_out_of_range = tile.clone();
+        no_data_out_of_range.header.no_data = Some(300.0);
+        let mut no_data_infinite = tile.clone();
+        no_data_infinite.header.dtype = DType::Float32;
+        no_data_infinite.payload = vec![0; 16];
+        no_data_infinite.header.no_data = Some(f64::INFINITY);
+        let mut empty = tile.clone();
+        empty.header.dimensions.rows = 0;
+        empty.payload.clear();
+
+        for (invalid, code) in [
+            (short_payload, TileErrorCode::InvalidPayloadLength),
+            (no_data_out_of_range, TileErrorCode::InvalidFieldValue),
+            (no_data_infinite, TileErrorCode::InvalidFieldValue),
+            (empty, TileErrorCode::InvalidFieldValue),
+        ] {
+            let err = invalid.assert_invariants().expect_err("broken invariant");
+            assert_eq!(err.code, code, "{}", err.message);
+        }
+    }
+
+    #[test]
+    fn compresses_with_each_deflate_strategy() {
+        let values: Vec<f64> = (0..1024).map(|i| f64::from(i) * 0.25).collect();
+        let payload = encode_payload_values(DType::Float32, Endianness::Little, &values)
+            .expect("encode payload values");
+        let input = TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Float32,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: TileDimensions {
+                rows: 32,
+                cols: 32,
+                bands: 1,
+            },
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+
+        let default = encode_tile(input.clone()).expect("default strategy");
+        let mut stored = Vec::new();
+        for strategy in [
+            DeflateStrategy::Default,
+            DeflateStrategy::Filtered,
+            DeflateStrategy::HuffmanOnly,
+            DeflateStrategy::Rle,
+        ] {
+            let options = EncodeOptions {
+                deflate_strategy: strategy,
+                ..EncodeOptions::default()
+            };
+            let encoded = encode_tile_with_options(input.clone(), &options)
+                .unwrap_or_else(|err| panic!("{strategy:?}: {err:?}"));
+            assert_eq!(encoded.header.compression, CompressionMode::DeflateRaw);
+            let decoded = decode_tile_minimal(&encoded.bytes).expect("decode strategy output");
+            assert_eq!(decoded.payload, payload, "{strategy:?}");
+            stored.push(encoded.bytes);
+        }
+        assert_eq!(stored[0], default.bytes);
+        assert_ne!(stored[2], stored[0], "Huffman-only output should differ");
+    }
+
+    #[test]
+    fn finds_duplicate_tile_ids_in_a_batch() {
+        let payload = [0_u8; 4];
+        let tile = |tile_id: u64, fill: u8| {
+            let payload = [fill; 4];
+            encode_tile(TileEncodeInput {
+                tile_id,
+                mesh_kind: MeshKind::JisX0410,
+                dtype: DType::Uint8,
+                endianness: Endianness::Little,
+                compression: CompressionMode::None,
+                dimensions: tile_dims(),
+                no_data: None,
+                payload: &payload,
+                extensions: ExtensionOptions::default(),
+            })
+            .expect("encode tile")
+            .bytes
+        };
+        let tiles = [
+            tile(9, 0),
+            tile(3, 0),
+            tile(9, 1),
+            tile(5, 0),
+            tile(3, 2),
+            tile(9, 3),
+        ];
+        let batch: Vec<&[u8]> = tiles.iter().map(Vec::as_slice).collect();
+        assert_eq!(
+            find_duplicate_tile_ids(&batch).expect("check batch"),
+            vec![3, 9]
+        );
+        assert_eq!(
+            find_duplicate_tile_ids(&batch[..2]).expect("no duplicates"),
+            Vec::<u64>::new()
+        );
+
+        // Only the fixed header is needed.
+        let header_only = &tiles[0][..TILE_FIXED_HEADER_LENGTH];
+        assert_eq!(
+            find_duplicate_tile_ids(&[header_only, header_only]).expect("headers only"),
+            vec![9]
+        );
+        let err = find_duplicate_tile_ids(&[&tiles[0], &payload]).expect_err("short tile");
+        assert!(
+            err.message.starts_with("Malformed tile 1:"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn detects_header_after_payload_layout() {
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &[1, 2, 3, 4],
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        assert_eq!(detect_layout(&encoded.bytes), LayoutHint::HeaderFirst);
+
+        let (header, payload) = encoded.bytes.split_at(TILE_FIXED_HEADER_LENGTH);
+        let reversed = [payload, header].concat();
+        assert_eq!(
+            decode_tile_minimal(&reversed)
+                .expect_err("reversed layout")
+                .code,
+            TileErrorCode::InvalidMagic
+        );
+        assert_eq!(detect_layout(&reversed), LayoutHint::HeaderLast);
+
+        assert_eq!(detect_layout(payload), LayoutHint::Unrecognized);
+        assert_eq!(detect_layout(&[]), LayoutHint::Unrecognized);
+    }
+
+    #[test]
+    fn compares_float_values_ignoring_zero_sign_and_nan_payloads() {
+        let tile = |dtype, endianness, values: &[f64]| {
+            let payload = encode_payload_values(dtype, endianness, values).expect("payload");
+            let encoded = encode_tile(TileEncodeInput {
+                tile_id: 42,
+                mesh_kind: MeshKind::JisX0410,
+                dtype,
+                endianness,
+                compression: CompressionMode::None,
+                dimensions: tile_dims(),
+                no_data: None,
+                payload: &payload,
+                extensions: ExtensionOptions::default(),
+            })
+            .expect("encode tile");
+            decode_tile_minimal(&encoded.bytes).expect("decode tile")
+        };
+        let quiet_nan = f64::from_bits(0x7ff8_0000_0000_0001);
+        for dtype in [DType::Float32, DType::Float64] {
+            let a = tile(dtype, Endianness::Little, &[0.0, -0.0, f64::NAN, 1.5]);
+            let b = tile(dtype, Endianness::Big, &[-0.0, 0.0, quiet_nan, 1.5]);
+            assert!(!a.payload_eq(&b));
+            assert!(
+                a.payload_values_bit_equal(&b).expect("compare"),
+                "{dtype:?}"
+            );
+            let c = tile(dtype, Endianness::Little, &[0.0, 0.0, f64::NAN, 2.5]);
+            assert!(
+                !a.payload_values_bit_equal(&c).expect("compare"),
+                "{dtype:?}"
+            );
+        }
+
+        let ints = tile(DType::Int16, Endianness::Little, &[0.0, -1.0, 2.0, 3.0]);
+        let swapped = tile(DType::Int16, Endianness::Big, &[0.0, -1.0, 2.0, 3.0]);
+        assert!(ints.payload_values_bit_equal(&swapped).expect("compare"));
+        let floats = tile(DType::Float32, Endianness::Little, &[0.0, -1.0, 2.0, 3.0]);
+        assert!(!ints.payload_values_bit_equal(&floats).expect("compare"));
+    }
+
+    #[test]
+    fn reinterprets_dtype_without_touching_payload() {
+        let payload =
+            encode_payload_values(DType::Uint16, Endianness::Big, &[1.0, 2.0, 3.0, 65535.0])
+                .expect("encode payload values");
+        let input = TileEncodeInput {
+            tile_id: 42,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint16,
+            endianness: Endianness::Big,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let encoded = encode_tile(input.clone()).expect("encode tile");
+
+        let mut bytes = encoded.bytes.clone();
+        reinterpret_dtype(&mut bytes, DType::Int16).expect("same width");
+        assert_eq!(
+            bytes[TILE_FIXED_HEADER_LENGTH..],
+            encoded.bytes[TILE_FIXED_HEADER_LENGTH..]
+        );
+        let decoded = decode_tile_minimal(&bytes).expect("decode relabeled tile");
+        assert_eq!(decoded.header.dtype, DType::Int16);
+        assert_eq!(decoded.header.endianness, Endianness::Big);
+        assert_eq!(decoded.header.payload_crc32, encoded.header.payload_crc32);
+        assert_eq!(
+            decode_payload_values(DType::Int16, Endianness::Big, &decoded.payload, None)
+                .expect("decode values"),
+            vec![Some(1.0), Some(2.0), Some(3.0), Some(-1.0)]
+        );
+
+        let err = reinterpret_dtype(&mut bytes, DType::Uint32).expect_err("wider dtype");
+        assert_eq!(err.code, TileErrorCode::InvalidFieldValue);
+
+        // A NaN no_data marker has no integer equivalent, so the tile is left unchanged.
+        let float_payload = [0_u8; 16];
+        let nan_tile = encode_tile(TileEncodeInput {
+            dtype: DType::Float32,
+            no_data: Some(f64::NAN),
+            payload: &float_payload,
+            ..input
+        })
+        .expect("encode float tile");
+        let mut bytes = nan_tile.bytes.clone();
+        assert!(reinterpret_dtype(&mut bytes, DType::Int32).is_err());
+        assert_eq!(bytes, nan_tile.bytes);
+    }
+
+    #[test]
+    fn computes_histograms_excluding_no_data() {
+        let dims = TileDimensions {
+            rows: 2,
+            cols: 3,
+            bands: 1,
+        };
+        let values = [0.0, 1.0, 2.0, 5.0, 10.0, 255.0];
+        let tile = decoded_tile(DType::Uint8, dims, &values, Some(255.0));
+
+        // Auto range is [0, 10]; 10 lands in the last bin.
+        assert_eq!(tile.histogram(2, None).expect("auto range"), vec![3, 2]);
+        assert_eq!(
+            tile.histogram(4, Some((0.0, 4.0))).expect("fixed range"),
+            vec![1, 1, 1, 0]
+        );
+        assert_eq!(
+            tile.histogram(1, Some((1.0, 1.0)))
+                .expect("single value range"),
+            vec![1]
+        );
+
+        let empty = decoded_tile(DType::Uint8, tile_dims(), &[255.0; 4], Some(255.0));
+        assert_eq!(
+            empty.histogram(3, None).expect("all no_data"),
+            vec![0, 0, 0]
+        );
+
+        assert_eq!(
+            tile.histogram(0, None).expect_err("no bins").code,
+            TileErrorCode::InvalidFieldValue
+        );
+        assert_eq!(
+            tile.histogram(2, Some((5.0, 1.0)))
+                .expect_err("inverted range")
+                .code,
+            TileErrorCode::InvalidFieldValue
+        );
+    }
+
+    #[test]
+    fn measures_header_overhead() {
+        let metadata = vec![(String::from("source"), String::from("survey"))];
+        let palette = [[0, 0, 0, 255], [255, 255, 255, 255]];
+        let input = TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: Some(0.0),
+            payload: &[0, 0, 0, 1],
+            extensions: ExtensionOptions::default(),
+        };
+        let plain = encode_tile(input.clone()).expect("encode tile");
+        assert_eq!(plain.header.header_overhead_fraction(), 58.0 / 62.0);
+
+        let extended = encode_tile_with_options(
+            TileEncodeInput {
+                extensions: ExtensionOptions {
+                    sparse: true,
+                    metadata: &metadata,
+                    geotransform: Some([139.0, 0.5, 0.0, 36.0, 0.0, -0.5]),
+                    band_crc32: true,
+                    payload_adler32: true,
+                    palette: Some(&palette),
+                },
+                ..input
+            },
+            &EncodeOptions {
+                format_minor: 1,
+                ..EncodeOptions::default()
+            },
+        )
+        .expect("encode tile with trailer");
+        assert!(extended.header.sparse);
+        assert_eq!(
+            extended.header.header_overhead_fraction(),
+            58.0 / extended.bytes.len() as f64
+        );
+    }
+
+    #[test]
+    fn cast_value_applies_policy() {
+        assert_eq!(
+            DType::Uint8.cast_value(7.0, CastPolicy::Reject).unwrap(),
+            7.0
+        );
+        assert!(DType::Uint8.cast_value(2.5, CastPolicy::Reject).is_err());
+        assert!(DType::Uint8.cast_value(300.0, CastPolicy::Reject).is_err());
+
+        assert_eq!(
+            DType::Int16.cast_value(2.5, CastPolicy::Round).unwrap(),
+            2.0
+        );
+        assert_eq!(
+            DType::Int16.cast_value(3.5, CastPolicy::Round).unwrap(),
+            4.0
+        );
+        assert_eq!(
+            DType::Int16.cast_value(-2.5, CastPolicy::Round).unwrap(),
+            -2.0
+        );
+        assert_eq!(
+            DType::Int16.cast_value(-2.6, CastPolicy::Round).unwrap(),
+            -3.0
+        );
+        assert!(DType::Uint8.cast_value(255.6, CastPolicy::Round).is_err());
+
+        assert_eq!(
+            DType::Uint8.cast_value(300.0, CastPolicy::Clamp).unwrap(),
+            255.0
+        );
+        assert_eq!(
+            DType::Uint8.cast_value(-1.0, CastPolicy::Clamp).unwrap(),
+            0.0
+        );
+        assert_eq!(
+            DType::Int24
+                .cast_value(f64::INFINITY, CastPolicy::Clamp)
+                .unwrap(),
+            8_388_607.0
+        );
+        assert!(DType::Uint8
+            .cast_value(f64::NAN, CastPolicy::Clamp)
+            .is_err());
+        assert_eq!(
+            DType::Float32.cast_value(1e39, CastPolicy::Clamp).unwrap(),
+            f32::MAX as f64
+        );
+        assert!(DType::Float32.cast_value(1e39, CastPolicy::Reject).is_err());
+        assert!(DType::Float64
+            .cast_value(f64::NAN, CastPolicy::Round)
+            .unwrap()
+            .is_nan());
+
+        let bytes = encode_payload_values_with_policy(
+            DType::Uint8,
+            Endianness::Little,
+            &[-4.0, 1.5, 999.0],
+            CastPolicy::Clamp,
+        )
+        .unwrap();
+        assert_eq!(bytes, [0, 2, 255]);
+    }
+
+    #[test]
+    fn computes_header_crc32_without_checksum_field() {
+        let payload = [1_u8, 2, 3, 4];
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let mut header: [u8; TILE_FIXED_HEADER_LENGTH] = encoded.bytes[..TILE_FIXED_HEADER_LENGTH]
+            .try_into()
+            .expect("header bytes");
+        assert_eq!(compute_header_crc32(&header), encoded.header.header_crc32);
+        header[HEADER_CHECKSUM_OFFSET..].fill(0);
+        assert_eq!(compute_header_crc32(&header), encoded.header.header_crc32);
+    }
+
+    #[test]
+    fn reads_single_sample_from_uncompressed_tile() {
+        let dimensions = TileDimensions {
+            rows: 2,
+            cols: 3,
+            bands: 2,
+        };
+        let values: Vec<f64> = (0..12).map(|value| value as f64 * 10.0).collect();
+        let payload =
+            encode_payload_values(DType::Int16, Endianness::Big, &values).expect("payload");
+        let input = |compression| TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Int16,
+            endianness: Endianness::Big,
+            compression,
+            dimensions,
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let encoded = encode_tile(input(CompressionMode::None)).expect("encode tile");
+
+        assert_eq!(
+            read_sample_uncompressed(&encoded.bytes, 0, 0, 0).unwrap(),
+            0.0
+        );
+        assert_eq!(
+            read_sample_uncompressed(&encoded.bytes, 1, 2, 1).unwrap(),
+            110.0
+        );
+        assert_eq!(
+            read_sample_uncompressed(&encoded.bytes, 1, 0, 1).unwrap(),
+            70.0
+        );
+        for (row, col, band) in [(2, 0, 0), (0, 3, 0), (0, 0, 2)] {
+            let error =
+                read_sample_uncompressed(&encoded.bytes, row, col, band).expect_err("out of range");
+            assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+        }
+
+        #[cfg(feature = "std")]
+        {
+            let compressed =
+                encode_tile(input(CompressionMode::DeflateRaw)).expect("encode compressed");
+            let error =
+                read_sample_uncompressed(&compressed.bytes, 0, 0, 0).expect_err("compressed tile");
+            assert_eq!(error.code, TileErrorCode::UnsupportedCompression);
+        }
+
+        // Forged dimensions whose sample index overflows u64 must not wrap around.
+        let mut forged = encoded.bytes.clone();
+        forged[OFFSET_ROWS..OFFSET_ROWS + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        forged[OFFSET_COLS..OFFSET_COLS + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        forged[OFFSET_BANDS] = 255;
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+        let error = read_sample_uncompressed(&forged, u32::MAX - 1, u32::MAX - 1, 254)
+            .expect_err("overflowing index");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+        assert!(error.message.contains("shorter"), "{}", error.message);
+    }
+
+    #[test]
+    fn maps_error_codes_to_stable_numbers() {
+        let all = [
+            TileErrorCode::InvalidMagic,
+            TileErrorCode::UnsupportedVersion,
+            TileErrorCode::InvalidHeaderLength,
+            TileErrorCode::InvalidFieldValue,
+            TileErrorCode::MissingRequiredField,
+            TileErrorCode::HeaderChecksumMismatch,
+            TileErrorCode::InvalidPayloadLength,
+            TileErrorCode::UnsupportedCompression,
+            TileErrorCode::CompressionFailed,
+            TileErrorCode::DecompressionFailed,
+            TileErrorCode::PayloadChecksumMismatch,
+            TileErrorCode::IoError,
+        ];
+        let mut numbers: Vec<u16> = all.iter().map(|code| code.numeric()).collect();
+        for code in all {
+            assert_eq!(TileErrorCode::from_numeric(code.numeric()), Some(code));
+        }
+        assert_eq!(TileErrorCode::InvalidMagic.numeric(), 1);
+        assert_eq!(TileErrorCode::IoError.numeric(), 12);
+        numbers.sort_unstable();
+        numbers.dedup();
+        assert_eq!(numbers.len(), all.len());
+        assert_eq!(TileErrorCode::from_numeric(0), None);
+        assert_eq!(TileErrorCode::from_numeric(13), None);
+    }
+
+    #[test]
+    fn encodes_shaped_data_with_length_check() {
+        let dimensions = TileDimensions {
+            rows: 2,
+            cols: 2,
+            bands: 2,
+        };
+        let data: [u16; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+        let payload = encode_from_shaped(DType::Uint16, Endianness::Little, &data, dimensions)
+            .expect("encode shaped data");
+        let values: Vec<f64> = data.iter().map(|&value| value.into()).collect();
+        assert_eq!(
+            payload,
+            encode_payload_values(DType::Uint16, Endianness::Little, &values).unwrap()
+        );
+
+        for short_or_long in [&data[..7], &[0_u16; 9][..]] {
+            let error =
+                encode_from_shaped(DType::Uint16, Endianness::Little, short_or_long, dimensions)
+                    .expect_err("mis-sized data");
+            assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+            assert!(error.message.contains("2x2x2 = 8"), "{}", error.message);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reencodes_with_retained_compressed_payload() {
+        let values: Vec<f64> = (0..64).map(|value| (value % 4) as f64).collect();
+        let payload =
+            encode_payload_values(DType::Uint8, Endianness::Little, &values).expect("payload");
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 3,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: TileDimensions {
+                rows: 8,
+                cols: 8,
+                bands: 1,
+            },
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let stored_len = encoded.header.payload_compressed_bytes as usize;
+        let stored =
+            &encoded.bytes[TILE_FIXED_HEADER_LENGTH..TILE_FIXED_HEADER_LENGTH + stored_len];
+
+        assert_eq!(
+            decode_tile_minimal(&encoded.bytes)
+                .expect("decode tile")
+                .compressed_payload,
+            None
+        );
+        let options = DecodeOptions {
+            retain_compressed_payload: true,
+            ..DecodeOptions::default()
+        };
+        let mut tile = decode_tile_with_options(&encoded.bytes, &options).expect("decode tile");
+        assert_eq!(tile.compressed_payload.as_deref(), Some(stored));
+
+        tile.header.metadata = vec![("source".into(), "survey".into())];
+        let edited = tile.reencode().expect("reencode");
+        assert_eq!(
+            &edited.bytes[TILE_FIXED_HEADER_LENGTH..TILE_FIXED_HEADER_LENGTH + stored_len],
+            stored
+        );
+        let redecoded = decode_tile_minimal(&edited.bytes).expect("decode edited tile");
+        assert_eq!(redecoded.payload, payload);
+        assert_eq!(redecoded.header.metadata, tile.header.metadata);
+
+        tile.payload[0] = 9;
+        let rewritten = tile.reencode().expect("reencode edited payload");
+        let redecoded = decode_tile_minimal(&rewritten.bytes).expect("decode rewritten tile");
+        assert_eq!(redecoded.payload, tile.payload);
+    }
+
+    #[test]
+    fn reencode_keeps_format_minor() {
+        let payload = vec![7_u8; 64];
+        let minor_one = EncodeOptions {
+            format_minor: 1,
+            ..EncodeOptions::default()
+        };
+        let options = DecodeOptions {
+            retain_compressed_payload: true,
+            ..DecodeOptions::default()
+        };
+        // Uncompressed tiles are encoded in full; deflated ones reuse the stored stream.
+        for compression in [CompressionMode::None, CompressionMode::DeflateRaw] {
+            let encoded = encode_tile_with_options(
+                TileEncodeInput {
+                    tile_id: 3,
+                    mesh_kind: MeshKind::JisX0410,
+                    dtype: DType::Uint8,
+                    endianness: Endianness::Little,
+                    compression,
+                    dimensions: TileDimensions {
+                        rows: 8,
+                        cols: 8,
+                        bands: 1,
+                    },
+                    no_data: None,
+                    payload: &payload,
+                    extensions: ExtensionOptions::default(),
+                },
+                &minor_one,
+            )
+            .expect("encode minor-1 tile");
+            let tile = decode_tile_with_options(&encoded.bytes, &options).expect("decode tile");
+            assert_eq!(tile.header.format_minor, 1);
+            let reencoded = tile.reencode().expect("reencode");
+            assert_eq!(reencoded.bytes, encoded.bytes, "{compression:?}");
+            assert_eq!(reencoded.header.format_minor, 1);
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_no_data_kind_early() {
+        let payload = [1_u8, 2, 3, 4];
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        let mut forged = encoded.bytes;
+        forged[OFFSET_NO_DATA_KIND] = 99;
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+
+        for error in [
+            parse_header_only(&forged).expect_err("parse forged header"),
+            decode_tile_minimal(&forged).expect_err("decode forged tile"),
+        ] {
+            assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+            assert!(
+                error.message.contains("no_data_kind 99")
+                    && error.message.contains("1 (numeric marker)"),
+                "{}",
+                error.message
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn compares_compression_with_entropy_bound() {
+        let dimensions = TileDimensions {
+            rows: 64,
+            cols: 64,
+            bands: 1,
+        };
+        // Every byte value equally often: 8 bits of entropy, but a repeating sequence.
+        let payload: Vec<u8> = (0..4096_u32).map(|i| (i * 37 % 256) as u8).collect();
+        let input = |compression| TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression,
+            dimensions,
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let stored = decode_tile_minimal(&encode_tile(input(CompressionMode::None)).unwrap().bytes)
+            .expect("decode stored tile");
+        let efficiency = stored.compression_efficiency().expect("stored bytes");
+        assert!((efficiency - 1.0).abs() < 1e-9);
+
+        let deflated = decode_tile_minimal(
+            &encode_tile(input(CompressionMode::DeflateRaw))
+                .unwrap()
+                .bytes,
+        )
+        .expect("decode deflated tile");
+        assert!(deflated.compression_efficiency().expect("stored bytes") > 1.0);
+
+        let mut forged = deflated;
+        forged.header.payload_compressed_bytes = 0;
+        assert_eq!(forged.compression_efficiency(), None);
+    }
+
+    #[test]
+    fn reports_max_tile_id_per_mesh_kind() {
+        let payload = [0_u8; 4];
+        let input = |tile_id| TileEncodeInput {
+            tile_id,
+            mesh_kind: MeshKind::Xyz,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        };
+        let max_xyz = max_tile_id(MeshKind::Xyz);
+        assert_eq!(max_xyz, (29 << 58) | ((1 << 58) - 1));
+        input(max_xyz).validate().expect("max xyz tile_id is valid");
+        let error = input(max_xyz + 1)
+            .validate()
+            .expect_err("zoom 30 is invalid");
+        assert_eq!(error.code, TileErrorCode::InvalidFieldValue);
+
+        assert_eq!(max_tile_id(MeshKind::JisX0410), u64::MAX);
+        assert_eq!(
+            max_tile_id(MeshKind::Custom(CUSTOM_MESH_KIND_MIN)),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn returns_payload_on_crc_mismatch_when_asked() {
+        let payload = [1_u8, 2, 3, 4];
+        let encoded = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::None,
+            dimensions: tile_dims(),
+            no_data: None,
+            payload: &payload,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode tile");
+        assert!(
+            decode_tile_minimal(&encoded.bytes)
+                .expect("decode tile")
+                .payload_crc_valid
+        );
+
+        let mut damaged = encoded.bytes;
+        damaged[TILE_FIXED_HEADER_LENGTH + 2] = 0xee;
+        let error = decode_tile_minimal(&damaged).expect_err("damaged payload");
+        assert_eq!(error.code, TileErrorCode::PayloadChecksumMismatch);
+
+        let forensic = DecodeOptions {
+            return_payload_on_crc_mismatch: true,
+            ..DecodeOptions::default()
+        };
+        let tile = decode_tile_with_options(&damaged, &forensic).expect("partial result");
+        assert!(!tile.payload_crc_valid);
+        assert_eq!(tile.payload, [1, 2, 0xee, 4]);
+    }
+
+    #[test]
+    fn word_swaps_match_scalar_byte_reversal() {
+        let bytes: Vec<u8> = (0..96_u32).map(|i| (i * 73 + 5) as u8).collect();
+        for value_size in [1, 2, 3, 4, 8] {
+            let mut swapped = bytes.clone();
+            raster::swap_sample_bytes(&mut swapped, value_size);
+            let mut expected = bytes.clone();
+            for sample in expected.chunks_exact_mut(value_size) {
+                sample.reverse();
+            }
+            assert_eq!(swapped, expected, "value_size={value_size}");
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stops_inflating_past_declared_length() {
+        let zeros = vec![0_u8; 1 << 20];
+        let big = encode_tile(TileEncodeInput {
+            tile_id: 1,
+            mesh_kind: MeshKind::JisX0410,
+            dtype: DType::Uint8,
+            endianness: Endianness::Little,
+            compression: CompressionMode::DeflateRaw,
+            dimensions: TileDimensions {
+                rows: 1024,
+                cols: 1024,
+                bands: 1,
+            },
+            no_data: None,
+            payload: &zeros,
+            extensions: ExtensionOptions::default(),
+        })
+        .expect("encode large tile");
+        let stream_len = big.header.payload_compressed_bytes as usize;
+        let stream = &big.bytes[TILE_FIXED_HEADER_LENGTH..TILE_FIXED_HEADER_LENGTH + stream_len];
+        assert!(stream.len() < 4096);
+
+        // A 2x2 tile whose small deflate stream expands to 1 MiB.
+        let bomb = encode_tile_precompressed(
+            TileEncodeInput {
+                tile_id: 1,
+                mesh_kind: MeshKind::JisX0410,
+                dtype: DType::Uint8,
+                endianness: Endianness::Little,
+                compression: CompressionMode::DeflateRaw,
+                dimensions: tile_dims(),
+                no_data: None,
+                payload: stream,
+                extensions: ExtensionOptions::default(),
+            },
+            crc32fast::hash(&[0; 4]),
+            4,
+        )
+        .expect("wrap stream");
+        let error = decode_tile_minimal(&bomb.bytes).expect_err("bomb should be rejected");
+        assert_eq!(error.code, TileErrorCode::InvalidPayloadLength);
+        assert!(
+            error.message.contains("inflates past 4 bytes"),
+            "{}",
+            error.message
+        );
+
+        // Forged dimensions (~8.7 TB of float64 samples) must not raise the limit.
+        let mut forged = bomb.bytes;
+        forged[OFFSET_ROWS..OFFSET_ROWS + 4].copy_from_slice(&65_535_u32.to_le_bytes());
+        forged[OFFSET_COLS..OFFSET_COLS + 4].copy_from_slice(&65_535_u32.to_le_bytes());
+        forged[OFFSET_BANDS] = 255;
+        forged[OFFSET_DTYPE_ENDIAN] = DType::Float64.code();
+        let header_crc32 = crc32fast::hash(&forged[..HEADER_CHECKSUM_INPUT_LENGTH]);
+        forged[HEADER_CHECKSUM_OFFSET..HEADER_CHECKSUM_OFFSET + 4]
+            .copy_from_slice(&header_crc32.to_le_bytes());
+        let error = decode_tile_minimal(&forged).expect_err("forged bomb should be rejected");
+        assert!(
+            error.message.contains("inflates past 4 bytes"),
+            "{}",
+            error.message
+        );
+
+        // Trusting the CRC over the length field allows the dense length, but only
+        // within max_uncompressed_bytes.
+        let trusting = DecodeOptions {
+            trust_payload_crc_over_length: true,
+            max_uncompressed_bytes: Some(1 << 16),
+            ..DecodeOptions::default()
+        };
+        let error = decode_tile_with_options(&forged, &trusting).expect_err("over the limit");
+        assert!(
+            error.message.contains("max_uncompressed_bytes"),
+            "{}",
+            error.message
+        );
+    }
 }
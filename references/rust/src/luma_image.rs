@@ -0,0 +1,62 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use image::{GrayImage, ImageBuffer, Luma};
+
+use crate::{decode_payload_values, DType, DecodedTile, Result, TileError, TileErrorCode};
+
+impl DecodedTile {
+    /// Converts a single-band uint8 tile to a grayscale image. No_data samples become 0.
+    pub fn to_luma_image(&self) -> Result<GrayImage> {
+        let samples = self.luma_samples(DType::Uint8)?;
+        let pixels = samples.into_iter().map(|v| v as u8).collect();
+        ImageBuffer::from_raw(
+            self.header.dimensions.cols,
+            self.header.dimensions.rows,
+            pixels,
+        )
+        .ok_or_else(image_size_error)
+    }
+
+    /// Converts a single-band uint16 tile to a 16-bit grayscale image. No_data samples
+    /// become 0.
+    pub fn to_luma16(&self) -> Result<ImageBuffer<Luma<u16>, Vec<u16>>> {
+        let samples = self.luma_samples(DType::Uint16)?;
+        let pixels = samples.into_iter().map(|v| v as u16).collect();
+        ImageBuffer::from_raw(
+            self.header.dimensions.cols,
+            self.header.dimensions.rows,
+            pixels,
+        )
+        .ok_or_else(image_size_error)
+    }
+
+    fn luma_samples(&self, dtype: DType) -> Result<Vec<f64>> {
+        if self.header.dimensions.bands != 1 || self.header.dtype != dtype {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "Luma image export requires a single-band {} tile, got {} bands of {}.",
+                    dtype.as_str(),
+                    self.header.dimensions.bands,
+                    self.header.dtype.as_str()
+                ),
+            ));
+        }
+        self.check_payload_length()?;
+        let values = decode_payload_values(
+            self.header.dtype,
+            self.header.endianness,
+            &self.payload,
+            self.header.no_data,
+        )?;
+        Ok(values.into_iter().map(|v| v.unwrap_or(0.0)).collect())
+    }
+}
+
+fn image_size_error() -> TileError {
+    TileError::new(
+        TileErrorCode::InvalidPayloadLength,
+        "Payload does not match image dimensions.",
+    )
+}
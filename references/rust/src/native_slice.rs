@@ -0,0 +1,63 @@
+use alloc::format;
+
+use bytemuck::Pod;
+
+use crate::common::native_endianness;
+use crate::{DType, DecodedTile, Result, TileError, TileErrorCode};
+
+impl DecodedTile {
+    pub fn as_slice_u8(&self) -> Result<&[u8]> {
+        self.native_slice(DType::Uint8)
+    }
+
+    pub fn as_slice_i8(&self) -> Result<&[i8]> {
+        self.native_slice(DType::Int8)
+    }
+
+    pub fn as_slice_u16(&self) -> Result<&[u16]> {
+        self.native_slice(DType::Uint16)
+    }
+
+    pub fn as_slice_i16(&self) -> Result<&[i16]> {
+        self.native_slice(DType::Int16)
+    }
+
+    pub fn as_slice_u32(&self) -> Result<&[u32]> {
+        self.native_slice(DType::Uint32)
+    }
+
+    pub fn as_slice_i32(&self) -> Result<&[i32]> {
+        self.native_slice(DType::Int32)
+    }
+
+    // Zero-copy view of the payload. Requires a matching dtype, host-native endianness,
+    // and a payload buffer aligned for `T`.
+    fn native_slice<T: Pod>(&self, dtype: DType) -> Result<&[T]> {
+        if self.header.dtype != dtype {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "Cannot view {} payload as {}.",
+                    self.header.dtype.as_str(),
+                    dtype.as_str()
+                ),
+            ));
+        }
+        if self.header.endianness != native_endianness() {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "Payload is {} but the host is {}; call into_native_endian first.",
+                    self.header.endianness.as_str(),
+                    native_endianness().as_str()
+                ),
+            ));
+        }
+        bytemuck::try_cast_slice(&self.payload).map_err(|err| {
+            TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                format!("Cannot view payload as {}: {err}.", dtype.as_str()),
+            )
+        })
+    }
+}
@@ -0,0 +1,759 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::checksum::crc32;
+use crate::common::{
+    expected_payload_length, is_no_data, native_endianness, read_numeric_value, sample_index,
+    write_numeric_value, xyz_children, xyz_parent,
+};
+use crate::decoder::{decode_tile_minimal, for_each_payload_value};
+use crate::encoder::{
+    build_header, encode_tile, encode_tile_precompressed_with_options, encode_tile_with_options,
+};
+use crate::extension::{palette_applies, Extensions};
+use crate::{
+    CompressionMode, DType, DecodedTile, EncodeOptions, EncodedTile, ExtensionOptions, MeshKind,
+    Result, TileDimensions, TileEncodeInput, TileError, TileErrorCode, TileHeader,
+};
+
+/// Builds a parent tile from up to four child tiles in `[top-left, top-right, bottom-left,
+/// bottom-right]` order: the children are assembled into a mosaic twice the child size,
+/// which is then averaged over 2x2 blocks down to the child size.
+///
+/// Present children must share dtype, dimensions, and no_data. No_data samples are left
+/// out of each average, and a block with no valid samples becomes no_data; missing
+/// children count as all no_data, which requires a no_data marker. Integer dtypes round
+/// the average half away from zero.
+///
+/// The parent takes its endianness, compression, and metadata from the first present
+/// child, and a geotransform is scaled to cover the whole mosaic. XYZ children must sit
+/// in their quadkey slot under one parent, whose tile_id the result gets; other mesh
+/// kinds keep the first present child's tile_id.
+pub fn mosaic_2x2_downsample(children: [Option<&[u8]>; 4]) -> Result<EncodedTile> {
+    let mut tiles: [Option<DecodedTile>; 4] = [None, None, None, None];
+    for (tile, bytes) in tiles.iter_mut().zip(children) {
+        if let Some(bytes) = bytes {
+            let decoded = decode_tile_minimal(bytes)?;
+            decoded.check_payload_length()?;
+            *tile = Some(decoded);
+        }
+    }
+    let (first_slot, first) = tiles
+        .iter()
+        .enumerate()
+        .find_map(|(slot, tile)| tile.as_ref().map(|tile| (slot, &tile.header)))
+        .ok_or_else(|| {
+            TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                "Mosaic needs at least one child tile.",
+            )
+        })?;
+
+    let dtype = first.dtype;
+    let dimensions = first.dimensions;
+    let no_data = first.no_data;
+    for header in tiles.iter().flatten().map(|tile| &tile.header) {
+        if header.dtype != dtype
+            || header.dimensions != dimensions
+            || header.no_data.map(f64::to_bits) != no_data.map(f64::to_bits)
+        {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                "Mosaic children must share dtype, dimensions, and no_data.",
+            ));
+        }
+    }
+    if no_data.is_none() && tiles.iter().any(Option::is_none) {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            "Mosaic children are missing but no_data is not set.",
+        ));
+    }
+
+    let tile_id = match first.mesh_kind {
+        MeshKind::Xyz => {
+            let parent = xyz_parent(first.tile_id)?.ok_or_else(|| {
+                TileError::new(
+                    TileErrorCode::InvalidFieldValue,
+                    "XYZ tiles at zoom 0 have no parent.",
+                )
+            })?;
+            let siblings = xyz_children(parent)?;
+            for (slot, tile) in tiles.iter().enumerate() {
+                if let Some(tile) = tile {
+                    if tile.header.mesh_kind != MeshKind::Xyz
+                        || tile.header.tile_id != siblings[slot]
+                    {
+                        return Err(TileError::new(
+                            TileErrorCode::InvalidFieldValue,
+                            format!(
+                                "Mosaic child {slot} has tile_id {} but XYZ parent {parent} expects {}.",
+                                tile.header.tile_id, siblings[slot]
+                            ),
+                        ));
+                    }
+                }
+            }
+            parent
+        }
+        _ => first.tile_id,
+    };
+
+    // Shift the first child's origin back to the mosaic's top-left corner and double the
+    // pixel size.
+    let (rows, cols, bands) = (
+        dimensions.rows as usize,
+        dimensions.cols as usize,
+        usize::from(dimensions.bands),
+    );
+    let geotransform = first.geotransform.map(|gt| {
+        let row_offset = ((first_slot / 2) * rows) as f64;
+        let col_offset = ((first_slot % 2) * cols) as f64;
+        [
+            gt[0] - col_offset * gt[1] - row_offset * gt[2],
+            gt[1] * 2.0,
+            gt[2] * 2.0,
+            gt[3] - col_offset * gt[4] - row_offset * gt[5],
+            gt[4] * 2.0,
+            gt[5] * 2.0,
+        ]
+    });
+
+    let sample_size = dtype.byte_size();
+    let endianness = first.endianness;
+    let mut payload = vec![0_u8; expected_payload_length(dimensions, dtype)?];
+    for row in 0..rows {
+        for col in 0..cols {
+            for band in 0..bands {
+                let (mut sum, mut count) = (0.0, 0_u32);
+                for mosaic_row in [2 * row, 2 * row + 1] {
+                    for mosaic_col in [2 * col, 2 * col + 1] {
+                        let slot = (mosaic_row / rows) * 2 + mosaic_col / cols;
+                        let Some(tile) = &tiles[slot] else {
+                            continue;
+                        };
+                        let index = ((mosaic_row % rows) * cols + mosaic_col % cols) * bands + band;
+                        let start = index * sample_size;
+                        let value = read_numeric_value(
+                            dtype,
+                            tile.header.endianness,
+                            &tile.payload[start..start + sample_size],
+                        )?;
+                        if no_data.is_some_and(|marker| is_no_data(value, marker)) {
+                            continue;
+                        }
+                        sum += value;
+                        count += 1;
+                    }
+                }
+                let value = match (count, no_data) {
+                    (0, Some(marker)) => marker,
+                    _ if matches!(dtype, DType::Float32 | DType::Float64) => sum / f64::from(count),
+                    _ => round_half_away(sum / f64::from(count)),
+                };
+                let start = ((row * cols + col) * bands + band) * sample_size;
+                write_numeric_value(
+                    dtype,
+                    endianness,
+                    value,
+                    true,
+                    &mut payload[start..start + sample_size],
+                )?;
+            }
+        }
+    }
+
+    encode_tile(TileEncodeInput {
+        tile_id,
+        mesh_kind: first.mesh_kind,
+        dtype,
+        endianness,
+        compression: first.compression,
+        dimensions,
+        no_data,
+        payload: &payload,
+        extensions: ExtensionOptions {
+            metadata: &first.metadata,
+            geotransform,
+            palette: first.palette.as_deref(),
+            ..ExtensionOptions::default()
+        },
+    })
+}
+
+/// Combines single-band tiles into one tile with a band per input, in input order and
+/// the §5 pixel interleave. Inputs must share tile_id, mesh kind, dimensions, dtype,
+/// endianness, and no_data; the result takes the first tile's metadata and geotransform,
+/// and its header describes the payload stored uncompressed. The inverse of
+/// [`DecodedTile::split_bands`].
+pub fn stack_bands(tiles: &[DecodedTile]) -> Result<DecodedTile> {
+    let first = &tiles
+        .first()
+        .ok_or_else(|| {
+            TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                "At least one tile is needed to stack bands.",
+            )
+        })?
+        .header;
+    let bands = u8::try_from(tiles.len()).map_err(|_| {
+        TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            format!("At most 255 bands can be stacked, got {}.", tiles.len()),
+        )
+    })?;
+    for (index, tile) in tiles.iter().enumerate() {
+        let header = &tile.header;
+        if header.dimensions.bands != 1 {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "Tile {index} has {} bands; only single-band tiles can be stacked.",
+                    header.dimensions.bands
+                ),
+            ));
+        }
+        if header.tile_id != first.tile_id
+            || header.mesh_kind != first.mesh_kind
+            || header.dimensions != first.dimensions
+            || header.dtype != first.dtype
+            || header.endianness != first.endianness
+            || header.no_data.map(f64::to_bits) != first.no_data.map(f64::to_bits)
+        {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!(
+                    "Tile {index} differs from tile 0 in tile_id, dimensions, dtype, endianness, or no_data."
+                ),
+            ));
+        }
+        tile.check_payload_length()?;
+    }
+
+    let dimensions = TileDimensions {
+        bands,
+        ..first.dimensions
+    };
+    let sample_size = first.dtype.byte_size();
+    let mut payload = Vec::with_capacity(expected_payload_length(dimensions, first.dtype)?);
+    for pixel in 0..tiles[0].payload.len() / sample_size {
+        let start = pixel * sample_size;
+        for tile in tiles {
+            payload.extend_from_slice(&tile.payload[start..start + sample_size]);
+        }
+    }
+
+    derive_tile(first, first.dtype, dimensions, first.no_data, payload)
+}
+
+// `f64::round` needs std; averages of integer samples are well within `i64`.
+fn round_half_away(value: f64) -> f64 {
+    if value >= 0.0 {
+        (value + 0.5) as i64 as f64
+    } else {
+        (value - 0.5) as i64 as f64
+    }
+}
+
+impl DecodedTile {
+    /// Resamples a window of this tile with nearest-neighbor sampling.
+    ///
+    /// `src_rect` is `(top, left, bottom, right)` in source pixel coordinates, where pixel
+    /// `(row, col)` covers `[row, row + 1) x [col, col + 1)`. Each output pixel takes the
+    /// source pixel under its center. Samples outside the source are filled with the
+    /// tile's no_data value; this is an error when the tile has no no_data marker.
+    ///
+    /// The returned tile has the same tile_id, dtype, endianness, bands, and no_data, and
+    /// its header describes the payload stored uncompressed. A geotransform is adjusted
+    /// to the window and output resolution.
+    pub fn resample_window(
+        &self,
+        src_rect: (f64, f64, f64, f64),
+        out_rows: u32,
+        out_cols: u32,
+    ) -> Result<DecodedTile> {
+        let (top, left, bottom, right) = src_rect;
+        if ![top, left, bottom, right].iter().all(|v| v.is_finite()) {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                "Resample window coordinates must be finite.",
+            ));
+        }
+
+        let source = self.header.dimensions;
+        let dtype = self.header.dtype;
+        let out_dimensions = TileDimensions {
+            rows: out_rows,
+            cols: out_cols,
+            bands: source.bands,
+        };
+        out_dimensions.validate()?;
+        self.check_payload_length()?;
+
+        let pixel_size = dtype.byte_size() * usize::from(source.bands);
+        let fill = match self.header.no_data {
+            Some(value) => {
+                let mut sample = vec![0_u8; dtype.byte_size()];
+                write_numeric_value(dtype, self.header.endianness, value, true, &mut sample)?;
+                Some(sample.repeat(usize::from(source.bands)))
+            }
+            None => None,
+        };
+
+        let mut payload = Vec::with_capacity(expected_payload_length(out_dimensions, dtype)?);
+        let row_step = (bottom - top) / f64::from(out_rows);
+        let col_step = (right - left) / f64::from(out_cols);
+        for out_row in 0..out_rows {
+            let src_row = source_index(top + (f64::from(out_row) + 0.5) * row_step, source.rows);
+            for out_col in 0..out_cols {
+                let src_col =
+                    source_index(left + (f64::from(out_col) + 0.5) * col_step, source.cols);
+                match (src_row, src_col) {
+                    (Some(row), Some(col)) => {
+                        let start = (row * source.cols as usize + col) * pixel_size;
+                        payload.extend_from_slice(&self.payload[start..start + pixel_size]);
+                    }
+                    _ => {
+                        let fill = fill.as_ref().ok_or_else(|| {
+                            TileError::new(
+                                TileErrorCode::InvalidFieldValue,
+                                "Resample window extends outside the tile but no_data is not set.",
+                            )
+                        })?;
+                        payload.extend_from_slice(fill);
+                    }
+                }
+            }
+        }
+
+        // The output grid covers the window, so its geotransform starts at the window's
+        // top-left corner with pixels scaled by the resampling step.
+        let mut header = self.header.clone();
+        header.geotransform = self.header.geotransform.map(|gt| {
+            [
+                gt[0] + left * gt[1] + top * gt[2],
+                gt[1] * col_step,
+                gt[2] * row_step,
+                gt[3] + left * gt[4] + top * gt[5],
+                gt[4] * col_step,
+                gt[5] * row_step,
+            ]
+        });
+        derive_tile(&header, dtype, out_dimensions, self.header.no_data, payload)
+    }
+
+    /// Applies `f` to every sample and stores the results as `dtype`, validating each value
+    /// against the dtype's range. No_data samples are kept as no_data and are not passed to
+    /// `f`; the no_data value itself must be representable in `dtype`. The returned header
+    /// describes the payload stored uncompressed.
+    pub fn map_values<F: FnMut(f64) -> f64>(&self, dtype: DType, mut f: F) -> Result<DecodedTile> {
+        self.check_payload_length()?;
+        let endianness = self.header.endianness;
+        let no_data = self.header.no_data;
+        let in_size = self.header.dtype.byte_size();
+        let out_size = dtype.byte_size();
+
+        let mut payload = vec![0_u8; self.payload.len() / in_size * out_size];
+        for (sample, out) in self
+            .payload
+            .chunks_exact(in_size)
+            .zip(payload.chunks_exact_mut(out_size))
+        {
+            let value = read_numeric_value(self.header.dtype, endianness, sample)?;
+            let mapped = match no_data {
+                Some(marker) if is_no_data(value, marker) => marker,
+                _ => f(value),
+            };
+            write_numeric_value(dtype, endianness, mapped, true, out)?;
+        }
+
+        derive_tile(
+            &self.header,
+            dtype,
+            self.header.dimensions,
+            no_data,
+            payload,
+        )
+    }
+
+    /// Maps a pixel position to world coordinates with the header's geotransform, or
+    /// `None` when the tile has none. `(0.0, 0.0)` is the top-left corner of the first
+    /// pixel; add `0.5` to each for pixel centers. Uses the GDAL convention:
+    /// `x = gt[0] + col * gt[1] + row * gt[2]`, `y = gt[3] + col * gt[4] + row * gt[5]`.
+    pub fn pixel_to_world(&self, row: f64, col: f64) -> Option<(f64, f64)> {
+        let gt = self.header.geotransform?;
+        Some((
+            gt[0] + col * gt[1] + row * gt[2],
+            gt[3] + col * gt[4] + row * gt[5],
+        ))
+    }
+
+    /// Sample index of `(row, col, band)` in the payload, `((row * cols) + col) * bands +
+    /// band` (spec §5), or `None` when any coordinate is out of range or the index does
+    /// not fit `usize`. Multiply by the dtype's byte size for a byte offset.
+    pub fn row_major_index(&self, row: u32, col: u32, band: u8) -> Option<usize> {
+        let (rows, cols, bands) = self.header.shape();
+        if row >= rows || col >= cols || band >= bands {
+            return None;
+        }
+        usize::try_from(sample_index(self.header.dimensions, row, col, band)?).ok()
+    }
+
+    /// Converts the payload to the host's byte order, byte-swapping every sample if the
+    /// tile is stored in the other endianness. Returns the tile unchanged when it is
+    /// already native; otherwise the header describes the payload stored uncompressed.
+    pub fn into_native_endian(self) -> Result<DecodedTile> {
+        let native = native_endianness();
+        if self.header.endianness == native {
+            return Ok(self);
+        }
+        self.check_payload_length()?;
+
+        let mut payload = self.payload;
+        swap_sample_bytes(&mut payload, self.header.dtype.byte_size());
+        let mut header = self.header;
+        header.endianness = native;
+        derive_tile(
+            &header,
+            header.dtype,
+            header.dimensions,
+            header.no_data,
+            payload,
+        )
+    }
+
+    /// Compares decoded payload bytes along with dtype, endianness, and dimensions,
+    /// ignoring how each tile was stored (compression mode and stored lengths).
+    pub fn payload_eq(&self, other: &DecodedTile) -> bool {
+        self.header.dtype == other.header.dtype
+            && self.header.endianness == other.header.endianness
+            && self.header.dimensions == other.header.dimensions
+            && self.payload == other.payload
+    }
+
+    /// Compares sample values along with dtype and dimensions, regardless of endianness or
+    /// how each tile was stored. Float samples are normalized first: `-0.0` equals `0.0`
+    /// and every NaN equals every other NaN. Integer samples compare exactly.
+    pub fn payload_values_bit_equal(&self, other: &DecodedTile) -> Result<bool> {
+        self.check_payload_length()?;
+        other.check_payload_length()?;
+        let dtype = self.header.dtype;
+        if dtype != other.header.dtype || self.header.dimensions != other.header.dimensions {
+            return Ok(false);
+        }
+
+        let canonical = |value: f64| {
+            if value == 0.0 {
+                0.0_f64.to_bits()
+            } else if value.is_nan() {
+                f64::NAN.to_bits()
+            } else {
+                value.to_bits()
+            }
+        };
+        let size = dtype.byte_size();
+        for (a, b) in self
+            .payload
+            .chunks_exact(size)
+            .zip(other.payload.chunks_exact(size))
+        {
+            let a = read_numeric_value(dtype, self.header.endianness, a)?;
+            let b = read_numeric_value(dtype, other.header.endianness, b)?;
+            if canonical(a) != canonical(b) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Counts samples of all bands into `bins` equal-width bins over `range` (inclusive of
+    /// both ends), or over the tile's own min/max when `range` is `None`. No_data and NaN
+    /// samples, and samples outside `range`, are not counted, so a tile of only no_data
+    /// yields all-zero bins. `bins` must be nonzero.
+    pub fn histogram(&self, bins: usize, range: Option<(f64, f64)>) -> Result<Vec<u64>> {
+        if bins == 0 {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                "Histogram needs at least one bin.",
+            ));
+        }
+        let header = &self.header;
+        let no_data = header.no_data;
+        let is_valid = |value: f64| {
+            !value.is_nan() && !no_data.is_some_and(|marker| is_no_data(value, marker))
+        };
+
+        let (min, max) = match range {
+            Some((min, max)) => {
+                if !(min.is_finite() && max.is_finite() && min <= max) {
+                    return Err(TileError::new(
+                        TileErrorCode::InvalidFieldValue,
+                        format!("Histogram range ({min}, {max}) must be finite with min <= max."),
+                    ));
+                }
+                (min, max)
+            }
+            None => {
+                let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+                for_each_payload_value(header.dtype, header.endianness, &self.payload, |value| {
+                    if is_valid(value) {
+                        min = min.min(value);
+                        max = max.max(value);
+                    }
+                    Ok(())
+                })?;
+                (min, max)
+            }
+        };
+
+        let mut counts = vec![0_u64; bins];
+        let width = max - min;
+        for_each_payload_value(header.dtype, header.endianness, &self.payload, |value| {
+            if is_valid(value) && value >= min && value <= max {
+                let bin = if width > 0.0 {
+                    ((value - min) / width * bins as f64) as usize
+                } else {
+                    0
+                };
+                counts[bin.min(bins - 1)] += 1;
+            }
+            Ok(())
+        })?;
+        Ok(counts)
+    }
+
+    /// Splits the tile into one single-band tile per band, in band order. Each keeps the
+    /// tile_id, dtype, endianness, and no_data, and its header describes the band's
+    /// payload stored uncompressed.
+    pub fn split_bands(&self) -> Result<Vec<DecodedTile>> {
+        (0..self.header.dimensions.bands)
+            .map(|band| self.select_bands(&[band]))
+            .collect()
+    }
+
+    pub(crate) fn select_bands(&self, bands: &[u8]) -> Result<DecodedTile> {
+        let source = self.header.dimensions;
+        if let Some(&band) = bands.iter().find(|&&band| band >= source.bands) {
+            return Err(TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                format!("Band index {band} out of range for {} bands.", source.bands),
+            ));
+        }
+        let out_bands = u8::try_from(bands.len()).map_err(|_| {
+            TileError::new(
+                TileErrorCode::InvalidFieldValue,
+                "At most 255 bands can be selected.",
+            )
+        })?;
+        let out_dimensions = TileDimensions {
+            bands: out_bands,
+            ..source
+        };
+        out_dimensions.validate()?;
+        self.check_payload_length()?;
+
+        let sample_size = self.header.dtype.byte_size();
+        let pixel_size = sample_size * usize::from(source.bands);
+        let mut payload =
+            Vec::with_capacity(expected_payload_length(out_dimensions, self.header.dtype)?);
+        for pixel in self.payload.chunks_exact(pixel_size) {
+            for &band in bands {
+                let start = usize::from(band) * sample_size;
+                payload.extend_from_slice(&pixel[start..start + sample_size]);
+            }
+        }
+
+        derive_tile(
+            &self.header,
+            self.header.dtype,
+            out_dimensions,
+            self.header.no_data,
+            payload,
+        )
+    }
+
+    /// Checks that the tile can be re-encoded as-is: the same checks as
+    /// [`TileEncodeInput::validate`], covering dimensions, the tile_id for its mesh kind,
+    /// payload length, no_data range for the dtype, and trailer fields. Returns the first
+    /// violation. Useful for tiles assembled or edited by hand.
+    pub fn assert_invariants(&self) -> Result<()> {
+        let header = &self.header;
+        TileEncodeInput {
+            tile_id: header.tile_id,
+            mesh_kind: header.mesh_kind,
+            dtype: header.dtype,
+            endianness: header.endianness,
+            compression: CompressionMode::None,
+            dimensions: header.dimensions,
+            no_data: header.no_data,
+            payload: &self.payload,
+            extensions: ExtensionOptions {
+                metadata: &header.metadata,
+                geotransform: header.geotransform,
+                palette: header.palette.as_deref(),
+                ..ExtensionOptions::default()
+            },
+        }
+        .validate()
+    }
+
+    /// Encodes the tile with its header's settings: compression, sparse encoding, and the
+    /// trailer fields. If the tile still holds its retained
+    /// [`compressed_payload`](DecodedTile::compressed_payload) and the payload still matches
+    /// the stored CRC, those bytes are written verbatim instead of recompressing. Edited
+    /// payloads, sparse tiles, and tiles with band CRCs or an Adler-32 are encoded in full.
+    /// Either way the header's format minor version is kept.
+    pub fn reencode(&self) -> Result<EncodedTile> {
+        let header = &self.header;
+        let input = TileEncodeInput {
+            tile_id: header.tile_id,
+            mesh_kind: header.mesh_kind,
+            dtype: header.dtype,
+            endianness: header.endianness,
+            compression: header.compression,
+            dimensions: header.dimensions,
+            no_data: header.no_data,
+            payload: &self.payload,
+            extensions: ExtensionOptions {
+                sparse: header.sparse,
+                metadata: &header.metadata,
+                geotransform: header.geotransform,
+                band_crc32: header.band_crc32.is_some(),
+                payload_adler32: header.payload_adler32.is_some(),
+                palette: header.palette.as_deref(),
+            },
+        };
+        let reusable = self.compressed_payload.as_deref().filter(|_| {
+            header.compression != CompressionMode::None
+                && !input.extensions.sparse
+                && !input.extensions.band_crc32
+                && !input.extensions.payload_adler32
+                && crc32(&self.payload) == header.payload_crc32
+        });
+        let options = EncodeOptions {
+            format_minor: header.format_minor,
+            ..EncodeOptions::default()
+        };
+        match reusable {
+            Some(stored) => encode_tile_precompressed_with_options(
+                TileEncodeInput {
+                    payload: stored,
+                    ..input
+                },
+                header.payload_crc32,
+                self.payload.len(),
+                &options,
+            ),
+            None => encode_tile_with_options(input, &options),
+        }
+    }
+
+    pub(crate) fn check_payload_length(&self) -> Result<()> {
+        let expected = expected_payload_length(self.header.dimensions, self.header.dtype)?;
+        if self.payload.len() != expected {
+            return Err(TileError::new(
+                TileErrorCode::InvalidPayloadLength,
+                format!(
+                    "Decoded payload length mismatch. expected={expected} got={}",
+                    self.payload.len()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn derive_tile(
+    source: &TileHeader,
+    dtype: DType,
+    dimensions: TileDimensions,
+    no_data: Option<f64>,
+    payload: Vec<u8>,
+) -> Result<DecodedTile> {
+    dimensions.validate()?;
+    let expected = expected_payload_length(dimensions, dtype)?;
+    if payload.len() != expected {
+        return Err(TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!(
+                "Payload byte length mismatch. expected={expected} got={}",
+                payload.len()
+            ),
+        ));
+    }
+
+    let input = TileEncodeInput {
+        tile_id: source.tile_id,
+        mesh_kind: source.mesh_kind,
+        dtype,
+        endianness: source.endianness,
+        compression: CompressionMode::None,
+        dimensions,
+        no_data,
+        payload: &payload,
+        extensions: ExtensionOptions {
+            metadata: &source.metadata,
+            geotransform: source.geotransform,
+            ..ExtensionOptions::default()
+        },
+    };
+    let extensions = Extensions {
+        metadata: source.metadata.clone(),
+        geotransform: source.geotransform,
+        palette: source
+            .palette
+            .clone()
+            .filter(|_| palette_applies(dtype, dimensions.bands)),
+        ..Extensions::default()
+    };
+    let (_, header) = build_header(
+        &input,
+        payload.len(),
+        payload.len(),
+        crc32(&payload),
+        &extensions,
+    )?;
+    Ok(DecodedTile {
+        header,
+        payload,
+        compressed_payload: None,
+        payload_crc_valid: true,
+    })
+}
+
+fn source_index(coord: f64, len: u32) -> Option<usize> {
+    if coord < 0.0 || coord >= f64::from(len) {
+        return None;
+    }
+    Some(coord as usize)
+}
+
+/// Reverses the byte order of every `value_size`-byte sample. The 2-, 4-, and 8-byte cases
+/// swap whole words, which the compiler turns into SIMD byte shuffles on targets that have
+/// them and plain `bswap`s elsewhere; other widths reverse each sample byte by byte.
+pub(crate) fn swap_sample_bytes(payload: &mut [u8], value_size: usize) {
+    match value_size {
+        2 => swap_words::<2>(payload, |word| {
+            u16::from_ne_bytes(word).swap_bytes().to_ne_bytes()
+        }),
+        4 => swap_words::<4>(payload, |word| {
+            u32::from_ne_bytes(word).swap_bytes().to_ne_bytes()
+        }),
+        8 => swap_words::<8>(payload, |word| {
+            u64::from_ne_bytes(word).swap_bytes().to_ne_bytes()
+        }),
+        _ => {
+            for sample in payload.chunks_exact_mut(value_size) {
+                sample.reverse();
+            }
+        }
+    }
+}
+
+fn swap_words<const N: usize>(payload: &mut [u8], swap: impl Fn([u8; N]) -> [u8; N]) {
+    for sample in payload.chunks_exact_mut(N) {
+        let word: [u8; N] = (&*sample).try_into().expect("chunk of N bytes");
+        sample.copy_from_slice(&swap(word));
+    }
+}
@@ -0,0 +1,21 @@
+use alloc::borrow::Cow;
+
+use bytes::Bytes;
+
+use crate::decoder::decode_tile_borrowed;
+use crate::{DecodeOptions, DecodedTileBytes, Result};
+
+/// Decodes a tile held in a shared [`Bytes`] buffer. Uncompressed, dense payloads are
+/// returned as a zero-copy slice of `buf`; otherwise the decoded payload is a new buffer.
+/// Checksums are verified as in [`decode_tile_minimal`](crate::decode_tile_minimal).
+pub fn decode_tile_bytes(buf: Bytes) -> Result<DecodedTileBytes> {
+    let tile = decode_tile_borrowed(&buf, &DecodeOptions::default())?;
+    let payload = match tile.payload {
+        Cow::Borrowed(slice) => buf.slice_ref(slice),
+        Cow::Owned(vec) => Bytes::from(vec),
+    };
+    Ok(DecodedTileBytes {
+        header: tile.header,
+        payload,
+    })
+}
@@ -0,0 +1,99 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::common::{expected_payload_length, write_numeric_value};
+use crate::{DType, Endianness, Result, TileDimensions, TileError, TileErrorCode};
+
+// Sparse payload layout: a presence bitmask with one bit per sample (sample `i` is bit
+// `i % 8` of byte `i / 8`, set when the sample is stored) followed by the stored
+// samples in canonical order. Samples whose bytes equal the no_data marker are omitted.
+
+pub(crate) fn sparsify_payload(
+    payload: &[u8],
+    dtype: DType,
+    endianness: Endianness,
+    no_data: f64,
+) -> Result<Option<Vec<u8>>> {
+    let value_size = dtype.byte_size();
+    let marker = no_data_bytes(dtype, endianness, no_data)?;
+    let sample_count = payload.len() / value_size;
+    let mask_len = sample_count.div_ceil(8);
+
+    let mut out = vec![0_u8; mask_len];
+    for (idx, sample) in payload.chunks_exact(value_size).enumerate() {
+        if sample == marker.as_slice() {
+            continue;
+        }
+        out[idx / 8] |= 1 << (idx % 8);
+        out.extend_from_slice(sample);
+        if out.len() >= payload.len() {
+            // Not smaller than the dense payload; store it dense instead.
+            return Ok(None);
+        }
+    }
+    Ok(Some(out))
+}
+
+pub(crate) fn expand_sparse_payload(
+    sparse: &[u8],
+    dimensions: TileDimensions,
+    dtype: DType,
+    endianness: Endianness,
+    no_data: Option<f64>,
+) -> Result<Vec<u8>> {
+    let no_data = no_data.ok_or_else(|| {
+        TileError::new(
+            TileErrorCode::MissingRequiredField,
+            "Sparse tiles require a no_data marker.",
+        )
+    })?;
+    let marker = no_data_bytes(dtype, endianness, no_data)?;
+    let value_size = dtype.byte_size();
+    let dense_len = expected_payload_length(dimensions, dtype)?;
+    let sample_count = dense_len / value_size;
+    let mask_len = sample_count.div_ceil(8);
+
+    let (mask, mut samples) = sparse.split_at_checked(mask_len).ok_or_else(|| {
+        TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            "Sparse payload shorter than its presence bitmask.",
+        )
+    })?;
+    let stored_count: usize = mask.iter().map(|byte| byte.count_ones() as usize).sum();
+    if stored_count * value_size != samples.len() {
+        return Err(TileError::new(
+            TileErrorCode::InvalidPayloadLength,
+            format!(
+                "Sparse payload length mismatch. expected={} got={}",
+                mask_len + stored_count * value_size,
+                sparse.len()
+            ),
+        ));
+    }
+    if !sample_count.is_multiple_of(8) && mask[mask_len - 1] >> (sample_count % 8) != 0 {
+        return Err(TileError::new(
+            TileErrorCode::InvalidFieldValue,
+            "Sparse presence bitmask has bits set past the last sample.",
+        ));
+    }
+
+    let mut out = Vec::with_capacity(dense_len);
+    for idx in 0..sample_count {
+        if mask[idx / 8] & (1 << (idx % 8)) != 0 {
+            let (sample, rest) = samples.split_at(value_size);
+            out.extend_from_slice(sample);
+            samples = rest;
+        } else {
+            out.extend_from_slice(&marker);
+        }
+    }
+    Ok(out)
+}
+
+fn no_data_bytes(dtype: DType, endianness: Endianness, no_data: f64) -> Result<Vec<u8>> {
+    let mut marker = vec![0_u8; dtype.byte_size()];
+    // A NaN marker is the canonical NaN; other NaN bit patterns are stored as samples.
+    write_numeric_value(dtype, endianness, no_data, true, &mut marker)?;
+    Ok(marker)
+}
@@ -0,0 +1,29 @@
+use std::fs;
+use std::path::Path;
+
+use crate::{decode_tile_minimal, DecodedTile, EncodedTile, Result, TileError, TileErrorCode};
+
+/// Reads and decodes the tile stored at `path`. Read failures are `IoError`; the bytes
+/// are then decoded as by [`decode_tile_minimal`].
+pub fn read_tile_file<P: AsRef<Path>>(path: P) -> Result<DecodedTile> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|err| {
+        TileError::new(
+            TileErrorCode::IoError,
+            format!("Could not read tile file {}: {err}", path.display()),
+        )
+    })?;
+    decode_tile_minimal(&bytes)
+}
+
+/// Writes an encoded tile to `path`, replacing any existing file. Write failures are
+/// `IoError`.
+pub fn write_tile_file<P: AsRef<Path>>(path: P, tile: &EncodedTile) -> Result<()> {
+    let path = path.as_ref();
+    fs::write(path, &tile.bytes).map_err(|err| {
+        TileError::new(
+            TileErrorCode::IoError,
+            format!("Could not write tile file {}: {err}", path.display()),
+        )
+    })
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use mesh_data_tile::{decode_tile_minimal, CompressionMode};
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+        .join("test")
+        .join("fixtures")
+        .join(name)
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mesh-data-tile"))
+        .args(args)
+        .output()
+        .expect("run mesh-data-tile")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "command failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).expect("utf-8 stdout")
+}
+
+#[test]
+fn inspect_prints_header_summary() {
+    let path = fixture_path("compressed.tile");
+    let bytes = fs::read(&path).expect("read fixture");
+    let expected = decode_tile_minimal(&bytes).expect("decode fixture").header;
+
+    let output = run(&["inspect", path.to_str().expect("utf-8 path")]);
+    assert_eq!(stdout(&output).trim_end(), expected.describe());
+}
+
+#[test]
+fn decode_dumps_samples_as_csv() {
+    let path = fixture_path("uncompressed.tile");
+    let output = run(&["decode", path.to_str().expect("utf-8 path"), "--csv"]);
+    let text = stdout(&output);
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("row,col,band,value"));
+
+    let header = decode_tile_minimal(&fs::read(&path).expect("read fixture"))
+        .expect("decode fixture")
+        .header;
+    let samples = header.dimensions.total_samples().expect("sample count");
+    assert_eq!(lines.count() as u64, samples);
+}
+
+#[test]
+fn convert_reencodes_with_requested_compression() {
+    let input = fixture_path("uncompressed.tile");
+    let output_path =
+        std::env::temp_dir().join(format!("mesh-data-tile-cli-{}.tile", std::process::id()));
+    let output = run(&[
+        "convert",
+        input.to_str().expect("utf-8 path"),
+        output_path.to_str().expect("utf-8 path"),
+        "--compression",
+        "deflate-raw",
+    ]);
+    stdout(&output);
+
+    let source = decode_tile_minimal(&fs::read(&input).expect("read fixture")).expect("decode");
+    let converted =
+        decode_tile_minimal(&fs::read(&output_path).expect("read output")).expect("decode");
+    fs::remove_file(&output_path).expect("remove output");
+    assert_eq!(converted.header.compression, CompressionMode::DeflateRaw);
+    assert_eq!(converted.payload, source.payload);
+    assert_eq!(converted.header.tile_id, source.header.tile_id);
+}
+
+#[test]
+fn rejects_unknown_arguments_with_usage() {
+    let output = run(&["convert", "a", "b", "--compression", "zstd"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage:"));
+}
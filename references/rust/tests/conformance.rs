@@ -3,6 +3,7 @@ use std::path::PathBuf;
 
 use mesh_data_tile::{
     decode_payload_values, decode_tile_minimal, CompressionMode, DType, MeshKind, TileErrorCode,
+    XyzTileId,
 };
 
 fn fixture_path(name: &str) -> PathBuf {
@@ -67,6 +68,8 @@ fn decodes_xyz_fixtures_and_values() {
 
     let (zoom, x, y) = decode_xyz_tile_id(uncompressed.header.tile_id);
     assert_eq!((zoom, x, y), (12, 3639, 1612));
+    let typed = XyzTileId::try_from(uncompressed.header.tile_id).expect("valid XYZ tile_id");
+    assert_eq!((typed.zoom(), typed.x(), typed.y()), (zoom, x, y));
 
     assert_eq!(uncompressed.header.dtype, DType::Uint8);
     let values = decode_payload_values(